@@ -0,0 +1,270 @@
+use std::collections::HashMap;
+
+use crate::collectors::helpers::{
+    collector_client, collector_request_count, collector_request_get, label_name, label_property,
+    metrics, parse_duration, router_url,
+};
+
+// /routing/bgp/session and /routing/bgp/connection, RouterOS 7
+pub async fn run(
+    username: String,
+    password: String,
+    address: String,
+    port: u16,
+    check_ssl: bool,
+    config: crate::configuration::Settings,
+) -> Result<String, reqwest::Error> {
+    let mut ret = "".to_owned();
+
+    // Perform requests
+    let sessions = collector_request_get(
+        router_url(
+            &username,
+            &password,
+            &address,
+            port,
+            "rest/routing/bgp/session",
+        ),
+        check_ssl,
+    )
+    .await?;
+    let connections = collector_request_get(
+        router_url(
+            &username,
+            &password,
+            &address,
+            port,
+            "rest/routing/bgp/connection",
+        ),
+        check_ssl,
+    )
+    .await?;
+
+    // Installed prefixes are counted on the router, one request per session over one connection
+    let client = collector_client(check_ssl);
+    let url = |family: &str| {
+        router_url(
+            &username,
+            &password,
+            &address,
+            port,
+            &format!("rest/{}/route/print", family),
+        )
+    };
+    let url = (url("ip"), url("ipv6"));
+    let installed = installed_prefixes(&client, &url, &sessions).await?;
+
+    // Count of sessions with an uptime, and of prefix series
+    let mut uptimes = 0;
+    let mut prefix_count = 0;
+    for (count, session) in sessions.as_array().into_iter().flatten().enumerate() {
+        let labels = format!("session=\"{}\"", label_property(session, "name"));
+        let preamble = count != 0;
+
+        // Peer details, and why the session last went down
+        let mut info_labels = labels.clone();
+        for property in [
+            "connection",
+            "remote.address",
+            "remote.as",
+            "remote.id",
+            "local.address",
+            "local.as",
+            "local.id",
+            "last-error",
+        ] {
+            info_labels.push_str(&format!(
+                ",{}=\"{}\"",
+                label_name(property),
+                label_property(session, property)
+            ));
+        }
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            "bgp_session_info",
+            "1",
+            &info_labels,
+            "gauge",
+            "BGP session peer details",
+            "",
+            preamble,
+        ));
+
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            "bgp_session_established",
+            if session["established"].as_str() == Some("true") {
+                "1"
+            } else {
+                "0"
+            },
+            &labels,
+            "gauge",
+            "BGP session is established",
+            "Boolean",
+            preamble,
+        ));
+
+        // Only established sessions have an uptime
+        if let Some(uptime) = session["uptime"].as_str().and_then(parse_duration) {
+            ret.push_str(&metrics(
+                &config.metrics_prefix,
+                "bgp_session_uptime_seconds",
+                &uptime.to_string(),
+                &labels,
+                "gauge",
+                "Time since the BGP session was established",
+                "seconds",
+                uptimes != 0,
+            ));
+            uptimes += 1;
+        }
+
+        // Prefixes received from the peer, and of those, the ones installed as active routes.
+        // Advertised prefixes aren't exported, RouterOS only lists them per prefix in
+        // /routing/bgp/advertisements, and only with output.keep-sent-attributes
+        let mut prefixes = vec![];
+        if let Some(value) = session["prefix-count"]
+            .as_str()
+            .and_then(|v| v.parse().ok())
+        {
+            prefixes.push(("received", value));
+        }
+        if let Some(value) = session["name"].as_str().and_then(|n| installed.get(n)) {
+            prefixes.push(("installed", *value));
+        }
+        for (direction, value) in prefixes {
+            ret.push_str(&metrics(
+                &config.metrics_prefix,
+                "bgp_session_prefixes",
+                &value.to_string(),
+                &format!("{},direction=\"{}\"", labels, direction),
+                "gauge",
+                "BGP prefixes on the session",
+                "prefixes",
+                prefix_count != 0,
+            ));
+            prefix_count += 1;
+        }
+
+        // Messages and bytes, remote is received and local is sent
+        for (metric, help, unit) in [
+            ("messages", "BGP messages on the session", "messages"),
+            ("bytes", "BGP bytes on the session", "bytes"),
+        ] {
+            for (i, (side, direction)) in [("remote", "in"), ("local", "out")].iter().enumerate() {
+                if let Some(value) = session[format!("{}.{}", side, metric).as_str()].as_str() {
+                    ret.push_str(&metrics(
+                        &config.metrics_prefix,
+                        &format!("bgp_session_{}_total", metric),
+                        value,
+                        &format!("{},direction=\"{}\"", labels, direction),
+                        "counter",
+                        help,
+                        unit,
+                        preamble || i != 0,
+                    ));
+                }
+            }
+        }
+    }
+
+    // Configured connections
+    for (i, connection) in connections.as_array().into_iter().flatten().enumerate() {
+        let mut labels = format!("connection=\"{}\"", label_property(connection, "name"));
+        for property in [
+            "remote.address",
+            "remote.as",
+            "local.role",
+            "local.address",
+            "as",
+            "routing-table",
+            "templates",
+            "address-families",
+            "disabled",
+        ] {
+            labels.push_str(&format!(
+                ",{}=\"{}\"",
+                label_name(property),
+                label_property(connection, property)
+            ));
+        }
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            "bgp_connection_info",
+            "1",
+            &labels,
+            "gauge",
+            "BGP connection configuration",
+            "",
+            i != 0,
+        ));
+    }
+
+    Ok(ret)
+}
+
+// Active BGP routes per established session, by name, from /ip/route or /ipv6/route for the
+// peer's address family. Routes are attributed by belongs-to, which names the peer they were
+// received from, e.g. bgp-IP-192.0.2.1, so this holds for iBGP between loopbacks and multihop
+// peers, where the gateway isn't the peer. When none of a family's active BGP routes can be
+// attributed, that family's sessions are left out rather than reported as 0
+async fn installed_prefixes(
+    client: &reqwest::Client,
+    (ip_url, ipv6_url): &(String, String),
+    sessions: &serde_json::Value,
+) -> Result<HashMap<String, u64>, reqwest::Error> {
+    let mut installed = HashMap::new();
+    for (url, ipv6) in [(ip_url, false), (ipv6_url, true)] {
+        let mut family = HashMap::new();
+        // Routes counted by belongs-to, and whether any session needed counting
+        let (mut attributed, mut counted) = (0, false);
+        for session in sessions.as_array().into_iter().flatten() {
+            let peer = match session["remote.address"].as_str() {
+                Some(peer) if session["established"].as_str() == Some("true") => peer,
+                _ => continue,
+            };
+            if peer.is_empty() || peer.contains(':') != ipv6 {
+                continue;
+            }
+            let name = session["name"].as_str().unwrap_or("").to_string();
+            // Nothing received, so nothing installed, without counting the table
+            if session["prefix-count"].as_str() == Some("0") {
+                family.insert(name, 0);
+                continue;
+            }
+            let belongs_to = format!("bgp-{}-{}", if ipv6 { "IPv6" } else { "IP" }, peer);
+            if let Some(value) = collector_request_count(
+                client,
+                url.clone(),
+                vec![
+                    "bgp=true".to_string(),
+                    "active=true".to_string(),
+                    format!("belongs-to={}", belongs_to),
+                ],
+            )
+            .await?
+            {
+                attributed += value;
+                counted = true;
+                family.insert(name, value);
+            }
+        }
+
+        // Only if none were attributed, check that there were none to attribute
+        if counted && attributed == 0 {
+            let total = collector_request_count(
+                client,
+                url.clone(),
+                vec!["bgp=true".to_string(), "active=true".to_string()],
+            )
+            .await?;
+            if total != Some(0) {
+                continue;
+            }
+        }
+        installed.extend(family);
+    }
+
+    Ok(installed)
+}
@@ -0,0 +1,291 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use crate::collectors::helpers::{
+    collector_request_get, escape_label_value, label_property, metrics, parse_number, router_url,
+};
+
+// CAPsMAN for the legacy wireless package (caps-man) and the wifi package (wifi), as
+// (package, remote-cap, radio, registration-table). Only one is normally in use, the
+// other returns nothing
+const PACKAGES: [(&str, &str, &str, &str); 2] = [
+    (
+        "caps-man",
+        "rest/caps-man/remote-cap",
+        "rest/caps-man/radio",
+        "rest/caps-man/registration-table?.proplist=interface,ssid",
+    ),
+    (
+        "wifi",
+        "rest/interface/wifi/capsman/remote-cap",
+        "rest/interface/wifi/radio",
+        "rest/interface/wifi/registration-table?.proplist=interface,ssid",
+    ),
+];
+
+// States a CAP is managed in, caps-man reports Run and wifi Ok
+const RUNNING_STATES: [&str; 2] = ["run", "ok"];
+
+// /caps-man/remote-cap and /interface/wifi/capsman/remote-cap, with registrations per CAP
+pub async fn run(
+    username: String,
+    password: String,
+    address: String,
+    port: u16,
+    check_ssl: bool,
+    config: crate::configuration::Settings,
+) -> Result<String, reqwest::Error> {
+    let mut ret = "".to_owned();
+
+    // Perform requests
+    let mut caps = vec![];
+    // (package, radio MAC) of CAPs the controller has radios for but which aren't connected
+    let mut disconnected: Vec<(&str, String)> = vec![];
+    let mut registrations: BTreeMap<(&str, String, String, String), u64> = BTreeMap::new();
+    for (package, remote_cap, radio, registration_table) in PACKAGES {
+        let json = collector_request_get(
+            router_url(&username, &password, &address, port, remote_cap),
+            check_ssl,
+        )
+        .await?;
+        let connected: Vec<serde_json::Value> = json.as_array().cloned().unwrap_or_default();
+
+        // Which CAP each managed interface is on. The radios of a CAP that dropped off are
+        // still listed, which is what tells it apart from one that was never there
+        let radios = collector_request_get(
+            router_url(&username, &password, &address, port, radio),
+            check_ssl,
+        )
+        .await?;
+        let radios = radios.as_array().cloned().unwrap_or_default();
+        if connected.is_empty() && radios.is_empty() {
+            // Not in use, so no need to look at the clients
+            continue;
+        }
+        let interface_caps: HashMap<String, String> = radios
+            .iter()
+            .map(|r| {
+                (
+                    label_property(r, "interface"),
+                    radio_cap(r).unwrap_or_default(),
+                )
+            })
+            .collect();
+        for mac in disconnected_caps(&connected, &radios).into_values() {
+            disconnected.push((package, mac));
+        }
+        for cap in connected {
+            caps.push((package, cap));
+        }
+
+        // (package, cap, interface, ssid) => clients
+        let clients = collector_request_get(
+            router_url(&username, &password, &address, port, registration_table),
+            check_ssl,
+        )
+        .await?;
+        for client in clients.as_array().into_iter().flatten() {
+            let interface = label_property(client, "interface");
+            // Clients on the controller's own radios aren't on a CAP
+            let cap = match interface_caps.get(&interface) {
+                Some(cap) => cap.clone(),
+                None => continue,
+            };
+            *registrations
+                .entry((package, cap, interface, label_property(client, "ssid")))
+                .or_insert(0) += 1;
+        }
+    }
+
+    // (package, state) => CAPs
+    let mut states: BTreeMap<(&str, String), u64> = BTreeMap::new();
+    for (package, cap) in &caps {
+        *states
+            .entry((package, label_property(cap, "state")))
+            .or_insert(0) += 1;
+    }
+    for (i, ((package, state), value)) in states.iter().enumerate() {
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            "capsman_caps",
+            &value.to_string(),
+            &format!("package=\"{}\",state=\"{}\"", package, state),
+            "gauge",
+            "CAPs connected to the controller per state",
+            "caps",
+            i != 0,
+        ));
+    }
+
+    // Per CAP families, kept apart so each is contiguous
+    let mut info = "".to_owned();
+    let mut running_caps = "".to_owned();
+    let mut cap_radios = "".to_owned();
+    for (count, (package, cap)) in caps.iter().enumerate() {
+        // Identities are often left at the default, so CAPs are told apart by their MAC address
+        let mac = match cap["base-mac"].as_str() {
+            Some(mac) => escape_label_value(mac),
+            None => label_property(cap, "address"),
+        };
+        let labels = format!("package=\"{}\",mac=\"{}\"", package, mac);
+        let preamble = count != 0;
+
+        // caps-man reports board, wifi board-name
+        let board = match cap["board"].as_str() {
+            Some(board) => escape_label_value(board),
+            None => label_property(cap, "board-name"),
+        };
+        info.push_str(&metrics(
+            &config.metrics_prefix,
+            "capsman_cap_info",
+            "1",
+            &format!(
+                "{},identity=\"{}\",address=\"{}\",board=\"{}\",version=\"{}\",state=\"{}\"",
+                labels,
+                label_property(cap, "identity"),
+                label_property(cap, "address"),
+                board,
+                label_property(cap, "version"),
+                label_property(cap, "state")
+            ),
+            "gauge",
+            "CAP connected to the controller",
+            "",
+            preamble,
+        ));
+
+        let running = cap["state"]
+            .as_str()
+            .map(|s| RUNNING_STATES.contains(&s.to_lowercase().as_str()))
+            .unwrap_or(false);
+        running_caps.push_str(&metrics(
+            &config.metrics_prefix,
+            "capsman_cap_running",
+            if running { "1" } else { "0" },
+            &labels,
+            "gauge",
+            "CAP is managed by the controller",
+            "Boolean",
+            preamble,
+        ));
+
+        if let Some(value) = cap["radios"].as_str().and_then(parse_number) {
+            cap_radios.push_str(&metrics(
+                &config.metrics_prefix,
+                "capsman_cap_radios",
+                &value.to_string(),
+                &labels,
+                "gauge",
+                "Radios on the CAP managed by the controller",
+                "radios",
+                !cap_radios.is_empty(),
+            ));
+        }
+    }
+
+    // CAPs the controller still has radios for, but which aren't connected
+    for (count, (package, mac)) in disconnected.iter().enumerate() {
+        running_caps.push_str(&metrics(
+            &config.metrics_prefix,
+            "capsman_cap_running",
+            "0",
+            &format!("package=\"{}\",mac=\"{}\"", package, mac),
+            "gauge",
+            "CAP is managed by the controller",
+            "Boolean",
+            !caps.is_empty() || count != 0,
+        ));
+    }
+    ret.push_str(&info);
+    ret.push_str(&running_caps);
+    ret.push_str(&cap_radios);
+
+    for (i, ((package, cap, interface, ssid), value)) in registrations.iter().enumerate() {
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            "capsman_registrations",
+            &value.to_string(),
+            &format!(
+                "package=\"{}\",cap=\"{}\",interface=\"{}\",ssid=\"{}\"",
+                package, cap, interface, ssid
+            ),
+            "gauge",
+            "Clients registered per CAP, interface and SSID",
+            "clients",
+            i != 0,
+        ));
+    }
+
+    Ok(ret)
+}
+
+// The identity of the CAP a radio is on, caps-man reports remote-cap-identity and wifi cap
+fn radio_cap(radio: &serde_json::Value) -> Option<String> {
+    radio["remote-cap-identity"]
+        .as_str()
+        .or_else(|| radio["cap"].as_str())
+        .map(escape_label_value)
+}
+
+// (identity, radio MAC) of the CAPs with radios on the controller but not in remote-cap. A CAP
+// has no base MAC without a connection, so it's keyed by the lowest MAC of its radios
+fn disconnected_caps(
+    connected: &[serde_json::Value],
+    radios: &[serde_json::Value],
+) -> BTreeMap<String, String> {
+    let identities: HashSet<String> = connected
+        .iter()
+        .map(|cap| label_property(cap, "identity"))
+        .collect();
+
+    let mut disconnected: BTreeMap<String, String> = BTreeMap::new();
+    for radio in radios {
+        let identity = match radio_cap(radio) {
+            Some(identity) if !identities.contains(&identity) => identity,
+            _ => continue,
+        };
+        let mac = match radio["radio-mac"].as_str() {
+            Some(mac) => escape_label_value(mac),
+            None => continue,
+        };
+        let lowest = disconnected.entry(identity).or_insert_with(|| mac.clone());
+        if mac < *lowest {
+            *lowest = mac;
+        }
+    }
+
+    disconnected
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn disconnected_wifi_caps() {
+        let connected = vec![json!({"identity": "cap-lobby", "base-mac": "AA:BB:CC:00:00:10"})];
+        let radios = vec![
+            json!({"cap": "cap-lobby", "interface": "cap-wifi1", "radio-mac": "AA:BB:CC:00:00:11"}),
+            json!({"cap": "cap-attic", "interface": "cap-wifi2", "radio-mac": "AA:BB:CC:00:00:32"}),
+            json!({"cap": "cap-attic", "interface": "cap-wifi3", "radio-mac": "AA:BB:CC:00:00:31"}),
+            // Not on a CAP
+            json!({"interface": "wifi1", "radio-mac": "AA:BB:CC:00:00:01"}),
+        ];
+        assert_eq!(
+            disconnected_caps(&connected, &radios),
+            BTreeMap::from([("cap-attic".to_string(), "AA:BB:CC:00:00:31".to_string())])
+        );
+    }
+
+    #[test]
+    fn disconnected_caps_man_caps() {
+        let radios = vec![
+            json!({"remote-cap-identity": "cap-lobby", "interface": "cap1", "radio-mac": "AA:BB:CC:00:00:11"}),
+        ];
+        assert_eq!(
+            disconnected_caps(&[], &radios),
+            BTreeMap::from([("cap-lobby".to_string(), "AA:BB:CC:00:00:11".to_string())])
+        );
+        assert!(disconnected_caps(&[json!({"identity": "cap-lobby"})], &radios).is_empty());
+    }
+}
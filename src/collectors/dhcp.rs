@@ -0,0 +1,204 @@
+use std::collections::BTreeMap;
+use std::net::Ipv4Addr;
+
+use crate::collectors::helpers::{collector_request_get, label_property, metrics, router_url};
+
+// /ip/dhcp-server/lease, /ip/pool and /ip/pool/used
+pub async fn run(
+    username: String,
+    password: String,
+    address: String,
+    port: u16,
+    check_ssl: bool,
+    config: crate::configuration::Settings,
+) -> Result<String, reqwest::Error> {
+    let mut ret = "".to_owned();
+
+    // Perform requests, there can be thousands of leases so only ask for what's used
+    let leases = collector_request_get(
+        router_url(
+            &username,
+            &password,
+            &address,
+            port,
+            "rest/ip/dhcp-server/lease?.proplist=server,status,dynamic,address,mac-address,host-name",
+        ),
+        check_ssl,
+    )
+    .await?;
+    let pools = collector_request_get(
+        router_url(
+            &username,
+            &password,
+            &address,
+            port,
+            "rest/ip/pool?.proplist=name,ranges",
+        ),
+        check_ssl,
+    )
+    .await?;
+    let used = collector_request_get(
+        router_url(
+            &username,
+            &password,
+            &address,
+            port,
+            "rest/ip/pool/used?.proplist=pool",
+        ),
+        check_ssl,
+    )
+    .await?;
+
+    // (server, status, type) => leases
+    let leases = leases.as_array().cloned().unwrap_or_default();
+    let mut servers: BTreeMap<(String, String, &str), u64> = BTreeMap::new();
+    for lease in &leases {
+        let lease_type = match lease["dynamic"].as_str() {
+            Some("true") => "dynamic",
+            _ => "static",
+        };
+        *servers
+            .entry((
+                label_property(lease, "server"),
+                label_property(lease, "status"),
+                lease_type,
+            ))
+            .or_insert(0) += 1;
+    }
+    for (i, ((server, status, lease_type), value)) in servers.iter().enumerate() {
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            "dhcp_server_leases",
+            &value.to_string(),
+            &format!(
+                "server=\"{}\",status=\"{}\",type=\"{}\"",
+                server, status, lease_type
+            ),
+            "gauge",
+            "DHCP leases per server, status and type",
+            "leases",
+            i != 0,
+        ));
+    }
+
+    // Pool size from its ranges, and how many addresses are in use
+    let mut in_use: BTreeMap<String, u64> = BTreeMap::new();
+    for address in used.as_array().into_iter().flatten() {
+        *in_use.entry(label_property(address, "pool")).or_insert(0) += 1;
+    }
+    let pools = pools.as_array().cloned().unwrap_or_default();
+    let sizes = pools.iter().filter_map(|pool| {
+        Some((
+            label_property(pool, "name"),
+            pool["ranges"].as_str().and_then(pool_size)?,
+        ))
+    });
+    for (i, (name, size)) in sizes.enumerate() {
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            "ip_pool_size",
+            &size.to_string(),
+            &format!("pool=\"{}\"", name),
+            "gauge",
+            "Addresses in the IP pool",
+            "addresses",
+            i != 0,
+        ));
+    }
+    for (i, pool) in pools.iter().enumerate() {
+        let name = label_property(pool, "name");
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            "ip_pool_used",
+            &in_use.get(&name).unwrap_or(&0).to_string(),
+            &format!("pool=\"{}\"", name),
+            "gauge",
+            "Addresses in use from the IP pool",
+            "addresses",
+            i != 0,
+        ));
+    }
+
+    // Per lease metrics, only if enabled and there aren't too many leases
+    if !config.dhcp.leases.export(leases.len()) {
+        return Ok(ret);
+    }
+    for (i, lease) in leases.iter().enumerate() {
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            "dhcp_lease_info",
+            "1",
+            &format!(
+                "server=\"{}\",address=\"{}\",mac_address=\"{}\",host_name=\"{}\",status=\"{}\",dynamic=\"{}\"",
+                label_property(lease, "server"),
+                label_property(lease, "address"),
+                label_property(lease, "mac-address"),
+                label_property(lease, "host-name"),
+                label_property(lease, "status"),
+                label_property(lease, "dynamic")
+            ),
+            "gauge",
+            "DHCP lease",
+            "",
+            i != 0,
+        ));
+    }
+
+    Ok(ret)
+}
+
+// Number of addresses in a pool's ranges, e.g. 10.0.0.10-10.0.0.254,10.0.1.0/24
+// None if any range can't be parsed
+fn pool_size(ranges: &str) -> Option<u64> {
+    let mut size = 0;
+    for range in ranges.split(',').map(str::trim).filter(|r| !r.is_empty()) {
+        size += if let Some((start, end)) = range.split_once('-') {
+            let start = u32::from(start.trim().parse::<Ipv4Addr>().ok()?);
+            let end = u32::from(end.trim().parse::<Ipv4Addr>().ok()?);
+            (end.checked_sub(start)? as u64) + 1
+        } else if let Some((network, prefix)) = range.split_once('/') {
+            network.trim().parse::<Ipv4Addr>().ok()?;
+            let prefix: u32 = prefix.parse().ok().filter(|p| *p <= 32)?;
+            1u64 << (32 - prefix)
+        } else {
+            range.parse::<Ipv4Addr>().ok()?;
+            1
+        };
+    }
+    Some(size)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pool_sizes() {
+        assert_eq!(pool_size("10.0.0.10-10.0.0.254"), Some(245));
+        assert_eq!(pool_size("10.0.1.0/24"), Some(256));
+        assert_eq!(pool_size("10.0.0.1"), Some(1));
+        assert_eq!(pool_size("10.0.0.1-10.0.0.1"), Some(1));
+        assert_eq!(pool_size("0.0.0.0/0"), Some(1 << 32));
+        assert_eq!(
+            pool_size("10.0.0.10-10.0.0.254, 10.0.1.0/24,10.0.2.1"),
+            Some(502)
+        );
+        // Ranges that cross an octet
+        assert_eq!(pool_size("10.0.0.200-10.0.1.10"), Some(67));
+        assert_eq!(pool_size(""), Some(0));
+    }
+
+    #[test]
+    fn invalid_pools() {
+        for ranges in [
+            "10.0.0.254-10.0.0.10",
+            "10.0.0.0/33",
+            "foo/24",
+            "10.0.0.1-",
+            "10.0.0.1,bar",
+            "2001:db8::/64",
+        ] {
+            assert_eq!(pool_size(ranges), None, "{}", ranges);
+        }
+    }
+}
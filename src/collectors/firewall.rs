@@ -0,0 +1,279 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::collectors::helpers::{
+    collector_request_get, label_name, label_value, metrics, router_url,
+};
+
+// /ip/firewall or /ipv6/firewall
+#[derive(Clone, Copy, Debug)]
+pub enum Family {
+    Ip,
+    Ipv6,
+}
+
+// Rule tables under /<family>/firewall
+#[derive(Clone, Copy, Debug)]
+pub enum Table {
+    Filter,
+    Nat,
+    Mangle,
+    Raw,
+}
+
+impl Family {
+    pub const ALL: [Family; 2] = [Family::Ip, Family::Ipv6];
+
+    // Name of the collector that enables this family, e.g. ip_firewall
+    pub fn collector(&self) -> String {
+        format!("{}_firewall", self)
+    }
+
+    // Name of the collector that enables this family's address lists, e.g. ip_firewall_address_list
+    pub fn address_list_collector(&self) -> String {
+        format!("{}_firewall_address_list", self)
+    }
+
+    // Name of the collector that enables this family's route summary, e.g. ipv6_route
+    pub fn route_collector(&self) -> String {
+        format!("{}_route", self)
+    }
+}
+
+impl Table {
+    pub const ALL: [Table; 4] = [Table::Filter, Table::Nat, Table::Mangle, Table::Raw];
+
+    // Name of the collector that enables this table, e.g. ipv6_firewall_nat
+    pub fn collector(&self, family: Family) -> String {
+        format!("{}_firewall_{}", family, self)
+    }
+}
+
+impl fmt::Display for Family {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Family::Ip => write!(f, "ip"),
+            Family::Ipv6 => write!(f, "ipv6"),
+        }
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Table::Filter => write!(f, "filter"),
+            Table::Nat => write!(f, "nat"),
+            Table::Mangle => write!(f, "mangle"),
+            Table::Raw => write!(f, "raw"),
+        }
+    }
+}
+
+// Collects each of `tables` for one family. The tables are requested concurrently, and
+// share the <family>_firewall_rule_* and <family>_firewall_chain_* metrics
+#[allow(clippy::too_many_arguments)]
+pub async fn run(
+    family: Family,
+    tables: Vec<Table>,
+    username: String,
+    password: String,
+    address: String,
+    port: u16,
+    check_ssl: bool,
+    config: crate::configuration::Settings,
+) -> Result<String, reqwest::Error> {
+    let mut ret = "".to_owned();
+    let max_length = config.firewall.max_label_length;
+
+    // Perform requests
+    let responses = futures::future::join_all(tables.iter().map(|table| {
+        collector_request_get(
+            router_url(
+                &username,
+                &password,
+                &address,
+                port,
+                &format!("rest/{}/firewall/{}", family, table),
+            ),
+            check_ssl,
+        )
+    }))
+    .await;
+
+    // Count of rules, across all tables for the info metric
+    let mut count = 0;
+    // (table, chain, action) => (packets, bytes)
+    let mut aggregates: BTreeMap<(String, String, String), (u64, u64)> = BTreeMap::new();
+    // (table, chain) => rules
+    let mut rules_per_chain: BTreeMap<(String, String), u64> = BTreeMap::new();
+    for (table, json) in tables.iter().zip(responses) {
+        let json = json?;
+        if json.as_array().is_none() {
+            continue;
+        }
+
+        for (position, rule) in json.as_array().unwrap().iter().enumerate() {
+            // Counters are keyed by the rule's id, chain and position, so editing a rule
+            // doesn't change the series
+            let chain = label_value(&rule["chain"], max_length);
+            let mut labels = format!(
+                "rule_id=\"{}\",chain=\"{}\",position=\"{}\"",
+                label_value(&rule[".id"], max_length),
+                chain,
+                position
+            );
+            // Rule key with the table, for the metrics shared between tables
+            let key_labels = format!("table=\"{}\",{}", table, labels);
+            // Everything that describes the rule goes on the info metric
+            let mut info_labels = key_labels.clone();
+            for (k, v) in rule.as_object().unwrap() {
+                match k.as_str() {
+                    // Counters, state and the rule key are skipped
+                    "bytes" | "packets" | ".id" | "chain" | "invalid" | "disabled" | "dynamic" => {}
+                    _ => {
+                        let label = format!(
+                            ",{k}=\"{v}\"",
+                            k = label_name(k),
+                            v = label_value(v, max_length)
+                        );
+                        info_labels.push_str(&label);
+                        // Only add to the counters if allowed in config
+                        if config
+                            .firewall
+                            .labels
+                            .iter()
+                            .any(|l| l == k || *l == label_name(k))
+                        {
+                            labels.push_str(&label);
+                        }
+                    }
+                }
+            }
+
+            // Rule info, preamble only on the first rule of any table
+            ret.push_str(&metrics(
+                &config.metrics_prefix,
+                &format!("{}_firewall_rule_info", family),
+                "1",
+                &info_labels,
+                "gauge",
+                "Firewall rule properties",
+                "",
+                count != 0,
+            ));
+
+            // Rule state, a disabled rule isn't matching anything, and an invalid rule refers to
+            // something that no longer exists, e.g. a deleted interface list
+            for (state, help) in [
+                ("disabled", "Firewall rule is disabled"),
+                ("invalid", "Firewall rule is invalid"),
+                ("dynamic", "Firewall rule is dynamic"),
+            ] {
+                let value = match rule.get(state).and_then(|v| v.as_str()) {
+                    Some("true") => "1",
+                    _ => "0",
+                };
+                ret.push_str(&metrics(
+                    &config.metrics_prefix,
+                    &format!("{}_firewall_rule_{}", family, state),
+                    value,
+                    &key_labels,
+                    "gauge",
+                    help,
+                    "",
+                    count != 0,
+                ));
+            }
+
+            // Aggregates per chain and action
+            let action = label_value(&rule["action"], max_length);
+            let aggregate = aggregates
+                .entry((table.to_string(), chain.clone(), action))
+                .or_insert((0, 0));
+            aggregate.0 += counter_value(rule, "packets");
+            aggregate.1 += counter_value(rule, "bytes");
+            *rules_per_chain
+                .entry((table.to_string(), chain))
+                .or_insert(0) += 1;
+
+            // Counters, preamble only on the first rule of this table
+            for (metric, help, unit) in [
+                ("packets", "Firewall rule packet count", "packets"),
+                ("bytes", "Firewall rule byte count", "bytes"),
+            ] {
+                if let Some(value) = rule.get(metric).and_then(|v| v.as_str()) {
+                    ret.push_str(&metrics(
+                        &config.metrics_prefix,
+                        &format!("{}_firewall_{}_{}_total", family, table, metric),
+                        value,
+                        &labels,
+                        "counter",
+                        help,
+                        unit,
+                        position != 0,
+                    ));
+                }
+            }
+
+            // Increase count
+            count += 1;
+        }
+    }
+
+    // Sum of the rule counters. This drops when a rule is removed, disabled or reset, which a
+    // counter would read as a reset, so it's a gauge
+    for (i, ((table, chain, action), (packets, bytes))) in aggregates.iter().enumerate() {
+        let labels = format!(
+            "table=\"{}\",chain=\"{}\",action=\"{}\"",
+            table, chain, action
+        );
+        for (metric, value, help, unit) in [
+            (
+                "packets",
+                packets,
+                "Firewall packet count per chain and action",
+                "packets",
+            ),
+            (
+                "bytes",
+                bytes,
+                "Firewall byte count per chain and action",
+                "bytes",
+            ),
+        ] {
+            ret.push_str(&metrics(
+                &config.metrics_prefix,
+                &format!("{}_firewall_chain_{}", family, metric),
+                &value.to_string(),
+                &labels,
+                "gauge",
+                help,
+                unit,
+                i != 0,
+            ));
+        }
+    }
+
+    for (i, ((table, chain), rules)) in rules_per_chain.iter().enumerate() {
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            &format!("{}_firewall_chain_rules", family),
+            &rules.to_string(),
+            &format!("table=\"{}\",chain=\"{}\"", table, chain),
+            "gauge",
+            "Firewall rules per chain",
+            "rules",
+            i != 0,
+        ));
+    }
+
+    Ok(ret)
+}
+
+// A rule's counter as a number, 0 if missing
+fn counter_value(rule: &serde_json::Value, counter: &str) -> u64 {
+    rule.get(counter)
+        .and_then(|v| v.as_str())
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(0)
+}
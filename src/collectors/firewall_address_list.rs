@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+
+use crate::collectors::firewall::Family;
+use crate::collectors::helpers::{
+    collector_request_get, label_value, metrics, parse_duration, router_url,
+};
+
+// /ip/firewall/address-list and /ipv6/firewall/address-list
+pub async fn run(
+    family: Family,
+    username: String,
+    password: String,
+    address: String,
+    port: u16,
+    check_ssl: bool,
+    config: crate::configuration::Settings,
+) -> Result<String, reqwest::Error> {
+    let mut ret = "".to_owned();
+    let max_length = config.firewall.max_label_length;
+
+    // Perform request, address lists can be large so only ask for what's used
+    let json = collector_request_get(
+        router_url(
+            &username,
+            &password,
+            &address,
+            port,
+            &format!(
+                "rest/{}/firewall/address-list?.proplist=list,address,dynamic,timeout",
+                family
+            ),
+        ),
+        check_ssl,
+    )
+    .await?;
+
+    let entries = match json.as_array() {
+        Some(entries) => entries,
+        None => return Ok(ret),
+    };
+
+    // list => (static, dynamic)
+    let mut lists: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+    for entry in entries {
+        let list = lists
+            .entry(label_value(&entry["list"], max_length))
+            .or_insert((0, 0));
+        if entry["dynamic"].as_str() == Some("true") {
+            list.1 += 1;
+        } else {
+            list.0 += 1;
+        }
+    }
+
+    for (i, (list, (static_entries, dynamic_entries))) in lists.iter().enumerate() {
+        for (entry_type, value) in [("static", static_entries), ("dynamic", dynamic_entries)] {
+            ret.push_str(&metrics(
+                &config.metrics_prefix,
+                &format!("{}_firewall_address_list_entries", family),
+                &value.to_string(),
+                &format!("list=\"{}\",type=\"{}\"", list, entry_type),
+                "gauge",
+                "Address list entries, dynamic entries have a timeout",
+                "entries",
+                i != 0 || entry_type != "static",
+            ));
+        }
+    }
+
+    // Per entry metrics, only if enabled and the address lists are small enough
+    if !config.firewall.address_list.export(entries.len()) {
+        return Ok(ret);
+    }
+
+    let mut timeouts = 0;
+    for (count, entry) in entries.iter().enumerate() {
+        let labels = format!(
+            "list=\"{}\",address=\"{}\"",
+            label_value(&entry["list"], max_length),
+            label_value(&entry["address"], max_length)
+        );
+        let dynamic = entry["dynamic"].as_str() == Some("true");
+
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            &format!("{}_firewall_address_list_entry", family),
+            "1",
+            &format!("{},dynamic=\"{}\"", labels, dynamic),
+            "gauge",
+            "Address list entry",
+            "",
+            count != 0,
+        ));
+
+        // Time left before a dynamic entry is removed
+        if let Some(timeout) = entry["timeout"].as_str().and_then(parse_duration) {
+            ret.push_str(&metrics(
+                &config.metrics_prefix,
+                &format!("{}_firewall_address_list_entry_timeout_seconds", family),
+                &timeout.to_string(),
+                &labels,
+                "gauge",
+                "Time until the address list entry is removed",
+                "seconds",
+                timeouts != 0,
+            ));
+            timeouts += 1;
+        }
+    }
+
+    Ok(ret)
+}
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 use std::error::Error;
 
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
 pub fn collector_preamble() -> (std::time::Instant, String, u8, String) {
     let now = std::time::Instant::now();
     let ret = "".to_string();
@@ -8,6 +10,30 @@ pub fn collector_preamble() -> (std::time::Instant, String, u8, String) {
     (now, ret, 0, labels)
 }
 
+// Characters escaped in the URL's userinfo, everything but the unreserved characters
+const USERINFO: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+// Build the REST URL for a router, bracketing IPv6 literals. Credentials are percent-encoded, as
+// passwords from secret files often have characters like @, :, / or #, reqwest decodes them again
+pub fn router_url(username: &str, password: &str, address: &str, port: u16, path: &str) -> String {
+    let host = match address.parse::<crate::target::Host>() {
+        Ok(h) => h.url_host(),
+        Err(_) => address.to_string(),
+    };
+    format!(
+        "https://{}:{}@{}:{}/{}",
+        utf8_percent_encode(username, USERINFO),
+        utf8_percent_encode(password, USERINFO),
+        host,
+        port,
+        path
+    )
+}
+
 pub async fn collector_request_get(
     url: String,
     check_ssl: bool,
@@ -52,6 +78,38 @@ pub async fn collector_request_post(
     }
 }
 
+// HTTP client for a collector that makes many requests, so they share one connection to the
+// router rather than each making its own
+pub fn collector_client(check_ssl: bool) -> reqwest::Client {
+    reqwest::Client::builder()
+        .danger_accept_invalid_certs(!check_ssl)
+        .build()
+        .unwrap()
+}
+
+// Count the entries matching `query` with <path>/print count-only, so the entries themselves are
+// never sent, e.g. for routing tables with a full BGP feed. Conditions in `query` are ANDed
+pub async fn collector_request_count(
+    client: &reqwest::Client,
+    url: String,
+    query: Vec<String>,
+) -> Result<Option<u64>, reqwest::Error> {
+    let request = client
+        .post(url)
+        .json(&serde_json::json!({ "count-only": "", ".query": query }))
+        .send()
+        .await?;
+
+    match request.status() {
+        reqwest::StatusCode::OK => {
+            // {"ret": "<count>"}
+            let json: serde_json::Value = request.json().await?;
+            Ok(json["ret"].as_str().and_then(|r| r.parse().ok()))
+        }
+        _ => Ok(None),
+    }
+}
+
 pub fn make_metric(prefix: &str, name: &str, labels: &str, value: &str) -> String {
     let mut r = "".to_owned();
 
@@ -61,6 +119,70 @@ pub fn make_metric(prefix: &str, name: &str, labels: &str, value: &str) -> Strin
     r
 }
 
+// A metric line, preceded by HELP/TYPE/UNIT unless supress_preamble is set
+#[allow(clippy::too_many_arguments)]
+pub fn metrics(
+    metric_prefix: &str,
+    name: &str,
+    value: &str,
+    labels: &str,
+    metric_type: &str,
+    help: &str,
+    unit: &str,
+    supress_preamble: bool,
+) -> String {
+    let mut ret = "".to_owned();
+
+    if !supress_preamble {
+        ret = format!(
+            "{}{}",
+            ret,
+            make_metric_preamble(metric_prefix, name, metric_type, help, unit)
+        );
+    }
+    ret = format!("{}{}", ret, make_metric(metric_prefix, name, labels, value));
+
+    ret
+}
+
+// A state set, one series per state with a state label, 1 for the current state. A current state
+// that isn't one of `states` gets a series as well
+pub fn state_set(
+    metric_prefix: &str,
+    name: &str,
+    labels: &str,
+    help: &str,
+    states: &[&str],
+    current: &str,
+    supress_preamble: bool,
+) -> String {
+    let mut ret = "".to_owned();
+
+    let mut states = states.to_vec();
+    if !states.contains(&current) {
+        states.push(current);
+    }
+    for (i, state) in states.iter().enumerate() {
+        let state = format!("state=\"{}\"", escape_label_value(state));
+        ret.push_str(&metrics(
+            metric_prefix,
+            name,
+            if states[i] == current { "1" } else { "0" },
+            &if labels.is_empty() {
+                state
+            } else {
+                format!("{},{}", labels, state)
+            },
+            "gauge",
+            help,
+            "",
+            supress_preamble || i != 0,
+        ));
+    }
+
+    ret
+}
+
 pub fn make_metric_preamble(
     prefix: &str,
     name: &str,
@@ -81,12 +203,272 @@ pub fn make_metric_preamble(
     r
 }
 
+// Escape a label value for the exposition format
+pub fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+// RouterOS property names to label names, e.g. src-address to src_address
+pub fn label_name(property: &str) -> String {
+    property
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '_' => c,
+            _ => '_',
+        })
+        .collect()
+}
+
+// Label value from a RouterOS property, truncated to max_length characters and escaped
+pub fn label_value(value: &serde_json::Value, max_length: usize) -> String {
+    let value = match value.as_str() {
+        Some(v) => v.to_string(),
+        None => value.to_string(),
+    };
+    escape_label_value(&value.chars().take(max_length).collect::<String>())
+}
+
+// A property of a RouterOS entry as an escaped label value, empty if missing
+pub fn label_property(entry: &serde_json::Value, property: &str) -> String {
+    escape_label_value(entry[property].as_str().unwrap_or(""))
+}
+
+// RouterOS durations to seconds, e.g. 1w2d3h4m5s, 5s300ms or 01:02:03
+pub fn parse_duration(original: &str) -> Option<f64> {
+    let original = original.trim();
+    if original.is_empty() {
+        return None;
+    }
+
+    // hh:mm:ss, optionally prefixed with days or weeks, e.g. 1d02:03:04
+    if let Some((prefix, time)) = original.rsplit_once(|c: char| c.is_ascii_alphabetic()) {
+        if time.contains(':') {
+            return Some(parse_duration(&format!("{}d", prefix))? + parse_duration(time)?);
+        }
+    }
+    if original.contains(':') {
+        let mut seconds = 0.0;
+        for part in original.split(':') {
+            seconds = seconds * 60.0 + part.parse::<f64>().ok()?;
+        }
+        return Some(seconds);
+    }
+
+    let mut seconds = 0.0;
+    let mut number = "".to_owned();
+    let mut chars = original.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            continue;
+        }
+        let unit = match (c, chars.peek()) {
+            ('m', Some('s')) => {
+                chars.next();
+                0.001
+            }
+            ('u', Some('s')) => {
+                chars.next();
+                0.000001
+            }
+            ('w', _) => 604800.0,
+            ('d', _) => 86400.0,
+            ('h', _) => 3600.0,
+            ('m', _) => 60.0,
+            ('s', _) => 1.0,
+            _ => return None,
+        };
+        seconds += number.parse::<f64>().ok()? * unit;
+        number.clear();
+    }
+    if !number.is_empty() {
+        return None;
+    }
+    Some(seconds)
+}
+
+// RouterOS date and time in the router's local time, to unix seconds
+// Accepts 2024-01-15 10:20:30 (7.10 onwards) and jan/15/2024 10:20:30
+pub fn parse_datetime(original: &str, gmt_offset: i64) -> Option<i64> {
+    let (date, time) = original.trim().split_once(' ')?;
+
+    let (year, month, day) = if let Some((month, rest)) = date.split_once('/') {
+        let month = [
+            "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+        ]
+        .iter()
+        .position(|m| m.eq_ignore_ascii_case(month))? as i64
+            + 1;
+        let (day, year) = rest.split_once('/')?;
+        (year.parse::<i64>().ok()?, month, day.parse::<i64>().ok()?)
+    } else {
+        let mut parts = date.split('-');
+        (
+            parts.next()?.parse::<i64>().ok()?,
+            parts.next()?.parse::<i64>().ok()?,
+            parts.next()?.parse::<i64>().ok()?,
+        )
+    };
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let mut parts = time.split(':');
+    let hours = parts.next()?.parse::<i64>().ok()?;
+    let minutes = parts.next()?.parse::<i64>().ok()?;
+    let seconds = parts.next()?.parse::<i64>().ok()?;
+
+    // Days since 1970-01-01, from Howard Hinnant's days_from_civil
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = if y >= 0 { y } else { y - 399 } / 400;
+    let yoe = y - era * 400;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+
+    Some(days * 86400 + hours * 3600 + minutes * 60 + seconds - gmt_offset)
+}
+
+// /system/clock gmt-offset to seconds, e.g. +01:00 or -05:30
+pub fn parse_gmt_offset(original: &str) -> Option<i64> {
+    let original = original.trim();
+    let (sign, offset) = if let Some(offset) = original.strip_prefix('+') {
+        (1, offset)
+    } else {
+        (-1, original.strip_prefix('-')?)
+    };
+    let (hours, minutes) = offset.split_once(':').unwrap_or((offset, "0"));
+    Some(sign * (hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60))
+}
+
+// Leading number of a RouterOS value, ignoring any unit, e.g. -2.5dBm or 35C
+pub fn parse_number(original: &str) -> Option<f64> {
+    let original = original.trim();
+    let end = original
+        .find(|c: char| !(c.is_ascii_digit() || c == '.' || c == '-' || c == '+'))
+        .unwrap_or(original.len());
+    original[..end].parse().ok()
+}
+
+// A RouterOS rate or limit in bits per second, e.g. 10M, 1.5G or 512000
+pub fn parse_rate(original: &str) -> Option<f64> {
+    let original = original.trim();
+    let (number, multiplier) = match original.chars().last()? {
+        'k' | 'K' => (&original[..original.len() - 1], 1000.0),
+        'M' => (&original[..original.len() - 1], 1000.0 * 1000.0),
+        'G' => (&original[..original.len() - 1], 1000.0 * 1000.0 * 1000.0),
+        _ => (original, 1.0),
+    };
+    number.parse::<f64>().ok().map(|n| n * multiplier)
+}
+
 pub fn convert_to_bps(original: &str) -> String {
     return if original.ends_with("Gbps") {
-        (original.replace("Gbps", "").parse::<f64>().unwrap_or(-1.0) * 1000.0 * 1000.0 * 1000.0).to_string()
+        (original.replace("Gbps", "").parse::<f64>().unwrap_or(-1.0) * 1000.0 * 1000.0 * 1000.0)
+            .to_string()
     } else if original.ends_with("Mbps") {
         (original.replace("Mbps", "").parse::<f64>().unwrap_or(-1.0) * 1000.0 * 1000.0).to_string()
     } else {
         0.to_string()
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn router_urls_escape_credentials() {
+        let url = router_url(
+            "ad@min",
+            "p@ss:w/rd#1 %",
+            "192.168.88.1",
+            8443,
+            "rest/ip/route",
+        );
+        let url = reqwest::Url::parse(&url).unwrap();
+        assert_eq!(url.host_str(), Some("192.168.88.1"));
+        assert_eq!(url.port(), Some(8443));
+        assert_eq!(url.path(), "/rest/ip/route");
+        assert_eq!(url.username(), "ad%40min");
+        assert_eq!(url.password(), Some("p%40ss%3Aw%2Frd%231%20%25"));
+
+        let url = router_url("admin", "secret", "2001:db8::1", 8443, "rest");
+        assert_eq!(url, "https://admin:secret@[2001:db8::1]:8443/rest");
+    }
+
+    #[test]
+    fn state_sets() {
+        assert_eq!(
+            state_set("p", "s", "a=\"1\"", "Help", &["up", "down"], "down", false),
+            "# HELP p_s Help\n# TYPE p_s gauge\n\
+             p_s{a=\"1\",state=\"up\"} 0\np_s{a=\"1\",state=\"down\"} 1\n"
+        );
+        assert_eq!(
+            state_set("p", "s", "", "Help", &["up"], "we\"ird", true),
+            "p_s{state=\"up\"} 0\np_s{state=\"we\\\"ird\"} 1\n"
+        );
+    }
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("1w2d3h4m5s"), Some(788645.0));
+        assert_eq!(parse_duration("5s300ms"), Some(5.3));
+        assert_eq!(parse_duration("250us"), Some(0.00025));
+        assert_eq!(parse_duration("1.5s"), Some(1.5));
+        assert_eq!(parse_duration(" 10m "), Some(600.0));
+        assert_eq!(parse_duration("01:02:03"), Some(3723.0));
+        assert_eq!(parse_duration("1d02:03:04"), Some(93784.0));
+        assert_eq!(parse_duration("1w1d00:00:01"), Some(691201.0));
+    }
+
+    #[test]
+    fn invalid_durations() {
+        for d in ["", "  ", "10", "5x", "s", "1h2", "01:xx:03", "never"] {
+            assert_eq!(parse_duration(d), None, "{}", d);
+        }
+    }
+
+    #[test]
+    fn datetimes() {
+        // 2024-01-15 10:20:30 UTC
+        let expected = 1705314030;
+        assert_eq!(parse_datetime("2024-01-15 10:20:30", 0), Some(expected));
+        assert_eq!(parse_datetime("jan/15/2024 10:20:30", 0), Some(expected));
+        assert_eq!(parse_datetime("Jan/15/2024 10:20:30", 0), Some(expected));
+        // Local time an hour ahead of UTC
+        assert_eq!(parse_datetime("2024-01-15 11:20:30", 3600), Some(expected));
+        assert_eq!(parse_datetime("1970-01-01 00:00:00", 0), Some(0));
+        assert_eq!(parse_datetime("2024-02-29 00:00:00", 0), Some(1709164800));
+        assert_eq!(parse_datetime("dec/31/1999 23:59:59", 0), Some(946684799));
+    }
+
+    #[test]
+    fn invalid_datetimes() {
+        for d in [
+            "",
+            "2024-01-15",
+            "2024-13-01 00:00:00",
+            "2024-00-01 00:00:00",
+            "2024-01-32 00:00:00",
+            "foo/15/2024 10:20:30",
+            "2024-01-15 10:20",
+            "2024-01-15 xx:20:30",
+        ] {
+            assert_eq!(parse_datetime(d, 0), None, "{}", d);
+        }
+    }
+
+    #[test]
+    fn gmt_offsets() {
+        assert_eq!(parse_gmt_offset("+01:00"), Some(3600));
+        assert_eq!(parse_gmt_offset("-05:30"), Some(-19800));
+        assert_eq!(parse_gmt_offset("+00:00"), Some(0));
+        assert_eq!(parse_gmt_offset("+10"), Some(36000));
+        assert_eq!(parse_gmt_offset("01:00"), None);
+        assert_eq!(parse_gmt_offset(""), None);
+    }
+}
@@ -2,34 +2,13 @@ use std::collections::HashMap;
 use std::error::Error;
 
 use crate::collectors::helpers::{
-    collector_preamble, collector_request_get, make_metric, make_metric_preamble,
+    collector_preamble, collector_request_get, escape_label_value, metrics, parse_datetime,
+    parse_gmt_offset, router_url,
 };
-
-pub fn metrics(
-    metric_prefix: &str,
-    name: &str,
-    value: &str,
-    labels: &str,
-    metric_type: &str,
-    help: &str,
-    unit: &str,
-    supress_preamble: bool,
-) -> String {
-    let mut ret = "".to_owned();
-
-    if !supress_preamble {
-        ret = format!(
-            "{}{}",
-            ret,
-            make_metric_preamble(metric_prefix, name, metric_type, help, unit)
-        );
-    }
-    ret = format!("{}{}", ret, make_metric(metric_prefix, name, labels, value));
-
-    ret
-}
+use crate::configuration::InterfaceMatcher;
 
 pub async fn run(
+    filter: InterfaceMatcher,
     username: String,
     password: String,
     address: String,
@@ -42,22 +21,47 @@ pub async fn run(
 
     // Perform request
     let json = collector_request_get(
-        format!(
-            "https://{}:{}@{}:{}/{}",
-            username, password, address, port, "rest/interface"
+        router_url(&username, &password, &address, port, "rest/interface"),
+        check_ssl,
+    )
+    .await?;
+
+    // Link up/down times are in the router's local time
+    let clock = collector_request_get(
+        router_url(
+            &username,
+            &password,
+            &address,
+            port,
+            "rest/system/clock?.proplist=gmt-offset",
         ),
         check_ssl,
     )
     .await?;
+    let gmt_offset = clock["gmt-offset"]
+        .as_str()
+        .and_then(parse_gmt_offset)
+        .unwrap_or(0);
 
     // Count of interfaces
     let mut count = 0;
     for interface in json.as_array().unwrap() {
+        // Skip interfaces excluded by the filter
+        if !filter.matches(
+            interface["name"].as_str().unwrap_or(""),
+            interface["type"].as_str().unwrap_or(""),
+            interface["comment"].as_str().unwrap_or(""),
+            interface["dynamic"].as_str() == Some("true"),
+        ) {
+            continue;
+        }
+
         labels = "".to_string();
-        // Inteface name added to labels
+        // Inteface name is the only label on the metrics, so editing a comment doesn't change
+        // the series
         labels.push_str(&*format!(
             "interface=\"{}\"",
-            interface["name"].as_str().unwrap()
+            escape_label_value(interface["name"].as_str().unwrap())
         ));
 
         let mut running = 0;
@@ -65,12 +69,15 @@ pub async fn run(
             running = 1;
         }
 
-        // If interface has a MAC Address, add it as a label
+        // Everything that describes the interface goes on the info metric
+        let mut info_labels = labels.clone();
+
+        // If interface has a MAC Address, add it to the info labels
         if interface.get("mac-address") != None {
             match interface["mac-address"].as_str().unwrap() {
                 "00:00:00:00:00:00" => {}
                 _ => {
-                    labels.push_str(&*format!(
+                    info_labels.push_str(&*format!(
                         ",mac_address=\"{}\"",
                         interface["mac-address"].as_str().unwrap()
                     ));
@@ -78,25 +85,17 @@ pub async fn run(
             }
         };
 
-        // If interface has a type, add it as a label
-        if interface.get("type") != None {
-            labels.push_str(&*format!(
-                ",type=\"{}\"",
-                interface["type"].as_str().unwrap()
-            ));
-        };
-
-        // If interface has is a slave, add it as a label
-        if interface.get("slave") != None {
-            labels.push_str(&*format!(",slave=\"{}\"", "true"));
-        };
-
-        // If interface has a comment, add it as a label
-        if interface.get("comment") != None {
-            // Strip "'s from output
-            let comment: String = interface["comment"].as_str().unwrap().replace("\"", "_");
-            if comment != "" { labels.push_str(&*format!(",comment=\"{}\"", comment)); }
-        };
+        // Type, comment, default-name and disabled, if the interface has them
+        for (property, label) in [
+            ("type", "type"),
+            ("comment", "comment"),
+            ("default-name", "default_name"),
+            ("disabled", "disabled"),
+        ] {
+            if let Some(value) = interface[property].as_str() {
+                info_labels.push_str(&*format!(",{}=\"{}\"", label, escape_label_value(value)));
+            }
+        }
 
         // Only do preamble on first interface
         let mut preamble: bool = false;
@@ -104,6 +103,22 @@ pub async fn run(
             preamble = true
         }
 
+        // Interface Info
+        ret = format!(
+            "{}{}",
+            ret,
+            metrics(
+                &config.metrics_prefix,
+                "interface_info",
+                "1",
+                &info_labels,
+                "gauge",
+                "Interface properties",
+                "",
+                preamble
+            )
+        );
+
         // Interface Running
         ret = format!(
             "{}{}",
@@ -153,16 +168,78 @@ pub async fn run(
                     "counter",
                 ],
             ),
-            // todo
-            // (
-            //     "disabled",
-            //     vec![
-            //         "interface_disabled",
-            //         "Interface disabled",
-            //         "status",
-            //         "gauge",
-            //     ],
-            // ),
+            (
+                "rx-packet",
+                vec![
+                    "interface_rx_packet_total",
+                    "Interface RX Packets",
+                    "packets",
+                    "counter",
+                ],
+            ),
+            (
+                "tx-packet",
+                vec![
+                    "interface_tx_packet_total",
+                    "Interface TX Packets",
+                    "packets",
+                    "counter",
+                ],
+            ),
+            (
+                "rx-error",
+                vec![
+                    "interface_rx_error_total",
+                    "Interface RX Errors",
+                    "packets",
+                    "counter",
+                ],
+            ),
+            (
+                "tx-error",
+                vec![
+                    "interface_tx_error_total",
+                    "Interface TX Errors",
+                    "packets",
+                    "counter",
+                ],
+            ),
+            (
+                "rx-drop",
+                vec![
+                    "interface_rx_drop_total",
+                    "Interface RX Drops",
+                    "packets",
+                    "counter",
+                ],
+            ),
+            (
+                "tx-drop",
+                vec![
+                    "interface_tx_drop_total",
+                    "Interface TX Drops",
+                    "packets",
+                    "counter",
+                ],
+            ),
+            (
+                "fp-rx-packet",
+                vec![
+                    "interface_fp_rx_packet_total",
+                    "Interface Fastpath RX Packets",
+                    "packets",
+                    "counter",
+                ],
+            ),
+            (
+                "fp-tx-packet",
+                vec![
+                    "interface_fp_tx_packet_total",
+                    "Interface Fastpath TX Packets",
+                    "packets",
+                    "counter",
+                ],
+            ),
             (
                 "fp-rx-byte",
                 vec![
@@ -233,37 +310,89 @@ pub async fn run(
                     "gauge",
                 ],
             ),
+            (
+                "disabled",
+                vec![
+                    "interface_disabled",
+                    "Interface disabled",
+                    "Boolean",
+                    "gauge",
+                ],
+            ),
+            (
+                "slave",
+                vec![
+                    "interface_slave",
+                    "Interface is a slave of a bridge or bond",
+                    "Boolean",
+                    "gauge",
+                ],
+            ),
+            (
+                "last-link-up-time",
+                vec![
+                    "interface_last_link_up_time_seconds",
+                    "Time the interface last came up, as a unix timestamp",
+                    "seconds",
+                    "gauge",
+                ],
+            ),
+            (
+                "last-link-down-time",
+                vec![
+                    "interface_last_link_down_time_seconds",
+                    "Time the interface last went down, as a unix timestamp",
+                    "seconds",
+                    "gauge",
+                ],
+            ),
         ]);
         // Iterate over metrics_hash
         for (metric, metric_attr) in mapped_metrics_hash {
-            let mut value = "";
+            let mut value = "".to_owned();
             if interface.get(metric) != None {
                 // match based on metric name
                 match metric {
                     "mtu" => {
                         if interface["mtu"] == "auto" {
-                            value = "65534"
+                            value = "65534".to_string()
                         } else {
-                            value = &interface[metric].as_str().unwrap()
+                            value = interface[metric].as_str().unwrap().to_string()
+                        }
+                    }
+                    "disabled" | "slave" => {
+                        value = match interface[metric].as_str() {
+                            Some("true") => "1".to_string(),
+                            _ => "0".to_string(),
+                        }
+                    }
+                    "last-link-up-time" | "last-link-down-time" => {
+                        if let Some(time) = interface[metric]
+                            .as_str()
+                            .and_then(|t| parse_datetime(t, gmt_offset))
+                        {
+                            value = time.to_string()
                         }
                     }
                     _ => {}
                 };
 
-                ret = format!(
-                    "{}{}",
-                    ret,
-                    metrics(
-                        &config.metrics_prefix,
-                        metric_attr[0],
-                        value,
-                        &labels.to_string(),
-                        metric_attr[3],
-                        metric_attr[1],
-                        metric_attr[2],
-                        preamble
+                if value != "" {
+                    ret = format!(
+                        "{}{}",
+                        ret,
+                        metrics(
+                            &config.metrics_prefix,
+                            metric_attr[0],
+                            &value,
+                            &labels.to_string(),
+                            metric_attr[3],
+                            metric_attr[1],
+                            metric_attr[2],
+                            preamble
+                        )
                     )
-                )
+                }
             }
         }
 
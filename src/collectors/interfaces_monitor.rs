@@ -1,35 +1,13 @@
 use crate::collectors::helpers::{
-    collector_preamble, collector_request_get, collector_request_post, convert_to_bps, make_metric,
-    make_metric_preamble,
+    collector_preamble, collector_request_get, collector_request_post, convert_to_bps,
+    escape_label_value, metrics, parse_number, router_url, state_set,
 };
+use crate::configuration::InterfaceMatcher;
 use std::collections::HashMap;
 use std::error::Error;
 
-pub fn metrics(
-    metric_prefix: &str,
-    name: &str,
-    value: &str,
-    labels: &str,
-    metric_type: &str,
-    help: &str,
-    unit: &str,
-    supress_preamble: bool,
-) -> String {
-    let mut ret = "".to_owned();
-
-    if !supress_preamble {
-        ret = format!(
-            "{}{}",
-            ret,
-            make_metric_preamble(metric_prefix, name, metric_type, help, unit)
-        );
-    }
-    ret = format!("{}{}", ret, make_metric(metric_prefix, name, labels, value));
-
-    ret
-}
-
 pub async fn run(
+    filter: InterfaceMatcher,
     username: String,
     password: String,
     address: String,
@@ -42,9 +20,12 @@ pub async fn run(
 
     // Perform request
     let json = collector_request_get(
-        format!(
-            "https://{}:{}@{}:{}/{}",
-            username, password, address, port, "rest/interface/ethernet?.proplist=.id"
+        router_url(
+            &username,
+            &password,
+            &address,
+            port,
+            "rest/interface/ethernet?.proplist=.id,name,comment",
         ),
         check_ssl,
     )
@@ -56,54 +37,57 @@ pub async fn run(
     // For each of the interface IDs, add it to interface
     if json.as_array() != None {
         for i in json.as_array().unwrap() {
+            // Skip interfaces excluded by the filter, ethernet interfaces are never dynamic
+            if !filter.matches(
+                i["name"].as_str().unwrap_or(""),
+                "ether",
+                i["comment"].as_str().unwrap_or(""),
+                false,
+            ) {
+                continue;
+            }
             if interfaces.len() > 0 {
                 interfaces.push_str(",")
             };
-            interfaces.push_str(&*i.get(".id").unwrap().to_string());
+            interfaces.push_str(i[".id"].as_str().unwrap_or(""));
+        }
+        if interfaces.is_empty() {
+            return Ok(ret);
         }
 
         // Build hashmap that we'll send as JSON for next request
         let mut map = HashMap::new();
         map.insert("numbers", interfaces.as_str());
-        map.insert("duration", "0.1s");
-        map.insert("interval", "0.1s");
+        map.insert("duration", config.monitor.duration.as_str());
+        map.insert("interval", config.monitor.interval.as_str());
 
         let json_poe = collector_request_post(
-            format!(
-                "https://{}:{}@{}:{}/{}",
-                username, password, address, port, "rest/interface/ethernet/monitor"
+            router_url(
+                &username,
+                &password,
+                &address,
+                port,
+                "rest/interface/ethernet/monitor",
             ),
             map,
             check_ssl,
         )
         .await?;
 
-        // Count of interfaces
-        let mut count = 0;
+        // Metrics exported so far, as not every interface has every metric, the count of those
+        // with an SFP module, and of the thresholds per reading
+        let mut emitted: Vec<&str> = vec![];
+        let mut sfp_count = 0;
+        let mut threshold_counts: HashMap<&str, u32> = HashMap::new();
         if json_poe.as_array() != None {
             for interface in json_poe.as_array().unwrap() {
                 labels = "".to_string();
                 // Inteface name added to labels
                 labels.push_str(&*format!(
                     "interface=\"{}\"",
-                    interface["name"].as_str().unwrap()
+                    escape_label_value(interface["name"].as_str().unwrap())
                 ));
-                if interface.get("mac-address") != None {
-                    match interface["mac-address"].as_str().unwrap() {
-                        "00:00:00:00:00:00" => {}
-                        _ => {
-                            labels.push_str(&*format!(
-                                ",mac-address={}",
-                                interface["mac-address"].as_str().unwrap()
-                            ));
-                        }
-                    }
-                }
-                // Only do preamble on first interface
-                let mut preamble: bool = false;
-                if count != 0 {
-                    preamble = true
-                }
+                // The MAC address is on interface_info, from the interfaces collector
 
                 // Simple metrics
                 let metrics_hash = HashMap::from([
@@ -148,32 +132,65 @@ pub async fn run(
                     ),
                 ]);
 
-                // TODO: if there's not been a preamble yet, help needs to be done
                 // Iterate over metrics_hash
                 for (metric, metric_attr) in metrics_hash {
-                    if interface.get(metric) != None {
+                    // Multi lane readings are exported per lane below, their first lane isn't
+                    // the reading for the whole module
+                    let reading = interface[metric].as_str().unwrap_or("");
+                    if lanes(reading).len() > 1 {
+                        continue;
+                    }
+                    if let Some(value) = parse_number(reading) {
                         ret = format!(
                             "{}{}",
                             ret,
                             metrics(
                                 &config.metrics_prefix,
                                 metric_attr[0],
-                                &interface[metric].as_str().unwrap(),
+                                &value.to_string(),
                                 &labels.to_string(),
                                 metric_attr[3],
                                 metric_attr[1],
                                 metric_attr[2],
-                                preamble
+                                seen(&mut emitted, metric_attr[0])
                             )
                         )
                     }
                 }
 
                 // Mapping Metrics
-                let mapped_metrics_hash = HashMap::from([(
-                    "rate",
-                    vec!["interface_rate", "Interface Speed", "bps", "gauge"],
-                )]);
+                let mapped_metrics_hash = HashMap::from([
+                    (
+                        "rate",
+                        vec!["interface_rate", "Interface Speed", "bps", "gauge"],
+                    ),
+                    (
+                        "full-duplex",
+                        vec![
+                            "interface_full_duplex",
+                            "Interface is running full duplex",
+                            "Boolean",
+                            "gauge",
+                        ],
+                    ),
+                    (
+                        "sfp-module-present",
+                        vec![
+                            "interface_sfp_module_present",
+                            "SFP Module Present",
+                            "Boolean",
+                            "gauge",
+                        ],
+                    ),
+                    (
+                        "sfp-rx-loss",
+                        vec!["interface_sfp_rx_loss", "SFP RX Loss", "Boolean", "gauge"],
+                    ),
+                    (
+                        "sfp-tx-fault",
+                        vec!["interface_sfp_tx_fault", "SFP TX Fault", "Boolean", "gauge"],
+                    ),
+                ]);
                 // Iterate over metrics_hash
                 for (metric, metric_attr) in mapped_metrics_hash {
                     let mut value = "".to_owned();
@@ -183,6 +200,13 @@ pub async fn run(
                             "rate" => {
                                 value = convert_to_bps(interface[metric].as_str().unwrap());
                             }
+                            "full-duplex" | "sfp-module-present" | "sfp-rx-loss"
+                            | "sfp-tx-fault" => {
+                                value = match interface[metric].as_str() {
+                                    Some("true") => "1".to_string(),
+                                    _ => "0".to_string(),
+                                }
+                            }
                             _ => {}
                         };
                         if value != "" {
@@ -197,18 +221,363 @@ pub async fn run(
                                     metric_attr[3],
                                     metric_attr[1],
                                     metric_attr[2],
-                                    preamble
+                                    seen(&mut emitted, metric_attr[0])
                                 )
                             )
                         }
                     }
                 }
 
-                // Increase count
-                count += 1;
+                // State sets, one series per state, 1 for the current state
+                let state_metrics_hash = HashMap::from([
+                    (
+                        "status",
+                        (
+                            "interface_status",
+                            "Interface link status",
+                            vec!["link-ok", "no-link", "unknown"],
+                        ),
+                    ),
+                    (
+                        "auto-negotiation",
+                        (
+                            "interface_auto_negotiation",
+                            "Interface auto negotiation state",
+                            vec!["done", "incomplete", "failed", "disabled"],
+                        ),
+                    ),
+                ]);
+                for (metric, (name, help, states)) in state_metrics_hash {
+                    if let Some(current) = interface[metric].as_str() {
+                        ret.push_str(&state_set(
+                            &config.metrics_prefix,
+                            name,
+                            &labels,
+                            help,
+                            &states,
+                            current,
+                            seen(&mut emitted, name),
+                        ));
+                    }
+                }
+
+                // Advertised speeds, e.g. 10M-half,100M-full,1G-full
+                for (metric, name, help) in [
+                    (
+                        "advertising",
+                        "interface_advertising",
+                        "Speeds advertised by the interface",
+                    ),
+                    (
+                        "link-partner-advertising",
+                        "interface_link_partner_advertising",
+                        "Speeds advertised by the link partner",
+                    ),
+                ] {
+                    if let Some(speeds) = interface[metric].as_str() {
+                        for speed in speeds.split(',').filter(|s| !s.is_empty()) {
+                            ret.push_str(&metrics(
+                                &config.metrics_prefix,
+                                name,
+                                "1",
+                                &format!("{},speed=\"{}\"", labels, escape_label_value(speed)),
+                                "gauge",
+                                help,
+                                "",
+                                seen(&mut emitted, name),
+                            ));
+                        }
+                    }
+                }
+
+                // Multi lane optics (QSFP) report a power reading per lane
+                for (metric, name, help) in [
+                    (
+                        "sfp-rx-power",
+                        "interface_sfp_lane_rx_power",
+                        "SFP RX Power per lane",
+                    ),
+                    (
+                        "sfp-tx-power",
+                        "interface_sfp_lane_tx_power",
+                        "SFP TX Power per lane",
+                    ),
+                ] {
+                    let lanes = lanes(interface[metric].as_str().unwrap_or(""));
+                    if lanes.len() > 1 {
+                        for (i, power) in lanes.iter().enumerate() {
+                            ret.push_str(&metrics(
+                                &config.metrics_prefix,
+                                name,
+                                &power.to_string(),
+                                &format!("{},lane=\"{}\"", labels, i + 1),
+                                "gauge",
+                                help,
+                                "dBm",
+                                seen(&mut emitted, name),
+                            ));
+                        }
+                    }
+                }
+
+                // Optics inventory, only for ports with a module plugged in
+                if interface["sfp-module-present"].as_str() == Some("true") {
+                    let mut info_labels = labels.clone();
+                    for (property, label) in [
+                        ("sfp-type", "sfp_type"),
+                        ("sfp-connector-type", "connector_type"),
+                        ("sfp-vendor-name", "vendor_name"),
+                        ("sfp-vendor-part-number", "vendor_part_number"),
+                        ("sfp-vendor-revision", "vendor_revision"),
+                        ("sfp-vendor-serial", "vendor_serial"),
+                        ("sfp-manufacturing-date", "manufacturing_date"),
+                    ] {
+                        info_labels.push_str(&format!(
+                            ",{}=\"{}\"",
+                            label,
+                            escape_label_value(interface[property].as_str().unwrap_or("").trim())
+                        ));
+                    }
+                    ret.push_str(&metrics(
+                        &config.metrics_prefix,
+                        "interface_sfp_info",
+                        "1",
+                        &info_labels,
+                        "gauge",
+                        "SFP module inventory",
+                        "",
+                        sfp_count != 0,
+                    ));
+
+                    // DOM thresholds, from the module's EEPROM
+                    let eeprom = eeprom_bytes(interface["eeprom"].as_str().unwrap_or(""));
+                    for (reading, unit, level, severity, value) in sfp_thresholds(&eeprom) {
+                        let thresholds = threshold_counts.entry(reading).or_insert(0);
+                        ret.push_str(&metrics(
+                            &config.metrics_prefix,
+                            &format!("interface_sfp_{}_threshold", reading),
+                            &value.to_string(),
+                            &format!("{},level=\"{}\",severity=\"{}\"", labels, level, severity),
+                            "gauge",
+                            "SFP DOM threshold reported by the module",
+                            unit,
+                            *thresholds != 0,
+                        ));
+                        *thresholds += 1;
+                    }
+                    sfp_count += 1;
+                }
             }
         }
     }
 
     Ok(ret)
 }
+
+// Whether `name` has been exported already, so its preamble is only written for its first series
+fn seen<'a>(emitted: &mut Vec<&'a str>, name: &'a str) -> bool {
+    if emitted.contains(&name) {
+        return true;
+    }
+    emitted.push(name);
+    false
+}
+
+// Readings per lane, e.g. -2.1,-2.3,-2.0,-2.2 for a QSFP module, single lane readings have one
+fn lanes(reading: &str) -> Vec<f64> {
+    reading
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter_map(parse_number)
+        .collect()
+}
+
+// The bytes of an EEPROM dump, as shown by RouterOS, e.g.
+//   0000: 03 04 07 10 00 00 00 00  00 00 00 06 67 00 00 00  ............g...
+// Each line has its offset, up to 16 bytes, and the bytes as text, which is ignored
+fn eeprom_bytes(dump: &str) -> Vec<u8> {
+    let mut bytes = vec![];
+    for line in dump.lines() {
+        let (offset, rest) = match line.split_once(':') {
+            Some((offset, rest)) => (offset.trim(), rest),
+            None => continue,
+        };
+        let offset = match usize::from_str_radix(offset, 16) {
+            Ok(offset) => offset,
+            Err(_) => continue,
+        };
+        // The bytes are in two groups of 8, separated from each other and the text by two
+        // spaces. The last line can be short, so stop at the first group that isn't all bytes
+        let mut line_bytes = vec![];
+        for group in rest.split("  ").map(str::trim).filter(|g| !g.is_empty()) {
+            let tokens = group.split_whitespace().count();
+            line_bytes.extend(group.split_whitespace().map_while(|b| match b.len() {
+                2 => u8::from_str_radix(b, 16).ok(),
+                _ => None,
+            }));
+            if line_bytes.len() % 8 != 0 || line_bytes.len() >= 16 || tokens % 8 != 0 {
+                break;
+            }
+        }
+        line_bytes.truncate(16);
+        if bytes.len() < offset + line_bytes.len() {
+            bytes.resize(offset + line_bytes.len(), 0);
+        }
+        bytes[offset..offset + line_bytes.len()].copy_from_slice(&line_bytes);
+    }
+    bytes
+}
+
+// Alarm and warning thresholds of an SFP module with digital diagnostics, as (reading, unit,
+// level, severity, value), in the same units as the monitor's readings. These are the first
+// 40 bytes of SFF-8472 page A2h, which follows the 256 bytes of page A0h in the dump
+fn sfp_thresholds(
+    eeprom: &[u8],
+) -> Vec<(&'static str, &'static str, &'static str, &'static str, f64)> {
+    // A0h byte 0 is the module type, 0x03 for SFP, and byte 92 the monitoring type. Bit 6 is set
+    // if diagnostics are implemented, and bit 5 if they're internally calibrated. Externally
+    // calibrated values need the module's calibration constants, so they're skipped
+    if eeprom.len() < 256 + 40 || eeprom[0] != 0x03 || eeprom[92] & 0x60 != 0x60 {
+        return vec![];
+    }
+    let a2 = &eeprom[256..];
+    let word = |i: usize| u16::from_be_bytes([a2[i], a2[i + 1]]);
+    // Power is in units of 0.1uW, exported as dBm. 0 has no dBm value
+    let dbm = |v: u16| match v {
+        0 => None,
+        _ => Some(10.0 * (v as f64 / 10000.0).log10()),
+    };
+
+    let mut thresholds = vec![];
+    for (i, (reading, unit)) in [
+        ("temperature", "celsius"),
+        ("supply_voltage", "volts"),
+        ("tx_bias_current", "mA"),
+        ("tx_power", "dBm"),
+        ("rx_power", "dBm"),
+    ]
+    .iter()
+    .enumerate()
+    {
+        for (j, (level, severity)) in [
+            ("high", "alarm"),
+            ("low", "alarm"),
+            ("high", "warning"),
+            ("low", "warning"),
+        ]
+        .iter()
+        .enumerate()
+        {
+            let raw = word(i * 8 + j * 2);
+            let value = match *reading {
+                // 1/256C, signed
+                "temperature" => Some(raw as i16 as f64 / 256.0),
+                // 100uV
+                "supply_voltage" => Some(raw as f64 / 10000.0),
+                // 2uA
+                "tx_bias_current" => Some(raw as f64 / 500.0),
+                _ => dbm(raw),
+            };
+            if let Some(value) = value {
+                // To 2 decimal places, as RouterOS shows the readings
+                let value = (value * 100.0).round() / 100.0;
+                thresholds.push((*reading, *unit, *level, *severity, value));
+            }
+        }
+    }
+    thresholds
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A0h and A2h of an SFP module with internally calibrated diagnostics, with the thresholds
+    // from SFF-8472's example values
+    fn dump() -> String {
+        let mut eeprom = vec![0u8; 512];
+        eeprom[0] = 0x03;
+        eeprom[92] = 0x68;
+        let thresholds: [u16; 20] = [
+            // Temperature 75C, -5C, 70C, 0C
+            0x4b00, 0xfb00, 0x4600, 0x0000, //
+            // Voltage 3.6V, 3.0V, 3.5V, 3.1V
+            36000, 30000, 35000, 31000, //
+            // Bias 15mA, 2mA, 12mA, 3mA
+            7500, 1000, 6000, 1500, //
+            // TX power 1mW, 0.1mW, 0.5mW, 0.2mW
+            10000, 1000, 5000, 2000, //
+            // RX power 1mW, 0.01mW, 0.5mW, unset
+            10000, 100, 5000, 0,
+        ];
+        for (i, t) in thresholds.iter().enumerate() {
+            eeprom[256 + i * 2..256 + i * 2 + 2].copy_from_slice(&t.to_be_bytes());
+        }
+        eeprom
+            .chunks(16)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+                format!(
+                    "{:04x}: {}  {}  ................",
+                    i * 16,
+                    hex[..8].join(" "),
+                    hex[8..].join(" ")
+                )
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn eeprom_dump() {
+        let bytes = eeprom_bytes("0000: 03 04 07  ..\n0010: ff 0a  AB CD");
+        assert_eq!(bytes.len(), 18);
+        assert_eq!(&bytes[..3], &[0x03, 0x04, 0x07]);
+        assert_eq!(&bytes[16..], &[0xff, 0x0a]);
+        assert_eq!(eeprom_bytes(&dump()).len(), 512);
+        assert!(eeprom_bytes("").is_empty());
+        assert!(eeprom_bytes("not a dump").is_empty());
+    }
+
+    #[test]
+    fn thresholds() {
+        let thresholds = sfp_thresholds(&eeprom_bytes(&dump()));
+        let get = |reading, level, severity| {
+            thresholds
+                .iter()
+                .find(|t| t.0 == reading && t.2 == level && t.3 == severity)
+                .map(|t| t.4)
+        };
+        assert_eq!(get("temperature", "high", "alarm"), Some(75.0));
+        assert_eq!(get("temperature", "low", "alarm"), Some(-5.0));
+        assert_eq!(get("supply_voltage", "low", "warning"), Some(3.1));
+        assert_eq!(get("tx_bias_current", "high", "warning"), Some(12.0));
+        assert_eq!(get("tx_power", "high", "alarm"), Some(0.0));
+        assert_eq!(get("tx_power", "low", "alarm"), Some(-10.0));
+        assert_eq!(get("rx_power", "low", "alarm"), Some(-20.0));
+        assert_eq!(get("rx_power", "high", "warning"), Some(-3.01));
+        assert_eq!(get("rx_power", "low", "warning"), None);
+        assert_eq!(thresholds.len(), 19);
+    }
+
+    #[test]
+    fn no_thresholds() {
+        let mut eeprom = eeprom_bytes(&dump());
+        // Externally calibrated
+        eeprom[92] = 0x58;
+        assert!(sfp_thresholds(&eeprom).is_empty());
+        // QSFP
+        eeprom[0] = 0x0d;
+        eeprom[92] = 0x68;
+        assert!(sfp_thresholds(&eeprom).is_empty());
+        // Page A0h only
+        assert!(sfp_thresholds(&eeprom_bytes(&dump())[..256]).is_empty());
+    }
+
+    #[test]
+    fn lanes_per_reading() {
+        assert_eq!(lanes("-2.1,-2.3,-2.0,-2.2"), vec![-2.1, -2.3, -2.0, -2.2]);
+        assert_eq!(lanes("-2.1"), vec![-2.1]);
+        assert!(lanes("").is_empty());
+    }
+}
@@ -1,35 +1,13 @@
 use crate::collectors::helpers::{
-    collector_preamble, collector_request_get, collector_request_post, make_metric,
-    make_metric_preamble,
+    collector_preamble, collector_request_get, collector_request_post, escape_label_value, metrics,
+    parse_number, router_url, state_set,
 };
+use crate::configuration::InterfaceMatcher;
 use std::collections::HashMap;
 use std::error::Error;
 
-pub fn metrics(
-    metric_prefix: &str,
-    name: &str,
-    value: &str,
-    labels: &str,
-    metric_type: &str,
-    help: &str,
-    unit: &str,
-    supress_preamble: bool,
-) -> String {
-    let mut ret = "".to_owned();
-
-    if !supress_preamble {
-        ret = format!(
-            "{}{}",
-            ret,
-            make_metric_preamble(metric_prefix, name, metric_type, help, unit)
-        );
-    }
-    ret = format!("{}{}", ret, make_metric(metric_prefix, name, labels, value));
-
-    ret
-}
-
 pub async fn run(
+    filter: InterfaceMatcher,
     username: String,
     password: String,
     address: String,
@@ -42,51 +20,93 @@ pub async fn run(
 
     // Perform request
     let json = collector_request_get(
-        format!(
-            "https://{}:{}@{}:{}/{}",
-            username, password, address, port, "rest/interface/ethernet/poe?.proplist=.id"
+        router_url(
+            &username,
+            &password,
+            &address,
+            port,
+            "rest/interface/ethernet/poe?.proplist=.id,name,poe-out,poe-priority,poe-voltage,power-cycle-ping-enabled",
+        ),
+        check_ssl,
+    )
+    .await?;
+
+    // Comments are on the ethernet interface, for the filter
+    let ethernet = collector_request_get(
+        router_url(
+            &username,
+            &password,
+            &address,
+            port,
+            "rest/interface/ethernet?.proplist=name,comment",
         ),
         check_ssl,
     )
     .await?;
+    let comment = |name: &str| -> String {
+        ethernet
+            .as_array()
+            .and_then(|e| e.iter().find(|i| i["name"].as_str() == Some(name)))
+            .and_then(|i| i["comment"].as_str())
+            .unwrap_or("")
+            .to_string()
+    };
 
     // Interfaces to send in next request
     let mut interfaces: String = "".to_string();
 
     // For each of the interface IDs, add it to interface
     if json.as_array() != None {
+        // Every port is monitored for the total power, the filter only applies to the per port metrics
         for i in json.as_array().unwrap() {
             if interfaces.len() > 0 {
                 interfaces.push_str(",")
             };
-            interfaces.push_str(&*i.get(".id").unwrap().to_string());
+            interfaces.push_str(i[".id"].as_str().unwrap_or(""));
+        }
+        if interfaces.is_empty() {
+            return Ok(ret);
         }
 
         // Build hashmap that we'll send as JSON for next request
         let mut map = HashMap::new();
         map.insert("numbers", interfaces.as_str());
-        map.insert("duration", "0.1s");
-        map.insert("interval", "0.1s");
+        map.insert("duration", config.monitor.duration.as_str());
+        map.insert("interval", config.monitor.interval.as_str());
 
         let json_poe = collector_request_post(
-            format!(
-                "https://{}:{}@{}:{}/{}",
-                username, password, address, port, "rest/interface/ethernet/poe/monitor"
+            router_url(
+                &username,
+                &password,
+                &address,
+                port,
+                "rest/interface/ethernet/poe/monitor",
             ),
             map,
             check_ssl,
         )
         .await?;
 
-        // Count of interfaces
+        // Count of exported interfaces, and the power drawn across all of them
         let mut count = 0;
+        let mut power_total = 0.0;
         if json_poe.as_array() != None {
             for interface in json_poe.as_array().unwrap() {
+                if let Some(power) = interface["poe-out-power"].as_str().and_then(parse_number) {
+                    power_total += power;
+                }
+
+                // Skip interfaces excluded by the filter, ethernet interfaces are never dynamic
+                let name = interface["name"].as_str().unwrap_or("");
+                if !filter.matches(name, "ether", &comment(name), false) {
+                    continue;
+                }
+
                 labels = "".to_string();
                 // Inteface name added to labels
                 labels.push_str(&*format!(
                     "interface=\"{}\"",
-                    interface["name"].as_str().unwrap()
+                    escape_label_value(interface["name"].as_str().unwrap())
                 ));
 
                 // Only do preamble on first interface
@@ -176,6 +196,69 @@ pub async fn run(
                     }
                 }
 
+                // Configured settings, from /interface/ethernet/poe
+                if let Some(settings) = json
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .find(|i| i["name"] == interface["name"])
+                {
+                    // State sets, one series per state, 1 for the current state
+                    for (setting, name, help, states) in [
+                        (
+                            "poe-out",
+                            "interface_poe_out_mode",
+                            "PoE Output mode",
+                            vec!["auto-on", "forced-on", "off"],
+                        ),
+                        (
+                            "poe-voltage",
+                            "interface_poe_voltage_setting",
+                            "PoE Output voltage setting",
+                            vec!["auto", "low", "high"],
+                        ),
+                    ] {
+                        if let Some(current) = settings[setting].as_str() {
+                            ret.push_str(&state_set(
+                                &config.metrics_prefix,
+                                name,
+                                &labels,
+                                help,
+                                &states,
+                                current,
+                                preamble,
+                            ));
+                        }
+                    }
+
+                    if let Some(priority) = settings["poe-priority"].as_str().and_then(parse_number)
+                    {
+                        ret.push_str(&metrics(
+                            &config.metrics_prefix,
+                            "interface_poe_priority",
+                            &priority.to_string(),
+                            &labels,
+                            "gauge",
+                            "PoE Output priority, lower is more important",
+                            "",
+                            preamble,
+                        ));
+                    }
+
+                    if let Some(enabled) = settings["power-cycle-ping-enabled"].as_str() {
+                        ret.push_str(&metrics(
+                            &config.metrics_prefix,
+                            "interface_poe_power_cycle_ping_enabled",
+                            if enabled == "true" { "1" } else { "0" },
+                            &labels,
+                            "gauge",
+                            "PoE Output is power cycled when ping fails",
+                            "Boolean",
+                            preamble,
+                        ));
+                    }
+                }
+
                 // // Mapping Metrics
                 // let mapped_metrics_hash = HashMap::from([
                 //     ("none", vec!("", "", "", "", ""))
@@ -210,6 +293,56 @@ pub async fn run(
                 count += 1;
             }
         }
+
+        // Power drawn across all PoE ports, whether or not the filter exports them
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            "poe_power_watts_total",
+            &power_total.to_string(),
+            "",
+            "gauge",
+            "PoE Output power across all ports",
+            "watts",
+            false,
+        ));
+
+        // Device budget and consumption, where the router reports them in /system/health
+        let health = collector_request_get(
+            router_url(&username, &password, &address, port, "rest/system/health"),
+            check_ssl,
+        )
+        .await?;
+        for (sensor, name, help) in [
+            (
+                "poe-out-consumption",
+                "poe_consumption_watts",
+                "PoE Output power reported by the router",
+            ),
+            (
+                "poe-out-budget",
+                "poe_budget_watts",
+                "PoE Output power budget reported by the router",
+            ),
+        ] {
+            let value = health.as_array().and_then(|h| {
+                h.iter()
+                    .find(|i| i["name"].as_str() == Some(sensor))
+                    .and_then(|i| i["value"].as_str())
+                    .and_then(parse_number)
+            });
+            if let Some(value) = value {
+                ret.push_str(&metrics(
+                    &config.metrics_prefix,
+                    name,
+                    &value.to_string(),
+                    "",
+                    "gauge",
+                    help,
+                    "watts",
+                    false,
+                ));
+            }
+        }
     }
 
     Ok(ret) //"".to_string()
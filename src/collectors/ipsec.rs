@@ -0,0 +1,247 @@
+use crate::collectors::helpers::{
+    collector_request_get, escape_label_value, label_property, metrics, parse_duration, router_url,
+};
+
+// /ip/ipsec/active-peers, /ip/ipsec/installed-sa and /ip/ipsec/statistics
+pub async fn run(
+    username: String,
+    password: String,
+    address: String,
+    port: u16,
+    check_ssl: bool,
+    config: crate::configuration::Settings,
+) -> Result<String, reqwest::Error> {
+    let mut ret = "".to_owned();
+
+    // Perform requests
+    let peers = collector_request_get(
+        router_url(
+            &username,
+            &password,
+            &address,
+            port,
+            "rest/ip/ipsec/active-peers",
+        ),
+        check_ssl,
+    )
+    .await?;
+    let sas = collector_request_get(
+        router_url(
+            &username,
+            &password,
+            &address,
+            port,
+            "rest/ip/ipsec/installed-sa",
+        ),
+        check_ssl,
+    )
+    .await?;
+    let statistics = collector_request_get(
+        router_url(
+            &username,
+            &password,
+            &address,
+            port,
+            "rest/ip/ipsec/statistics",
+        ),
+        check_ssl,
+    )
+    .await?;
+
+    // Count of peers with an uptime
+    let mut uptimes = 0;
+    for (count, peer) in peers.as_array().into_iter().flatten().enumerate() {
+        let labels = format!(
+            "local_address=\"{}\",remote_address=\"{}\"",
+            label_property(peer, "local-address"),
+            label_property(peer, "remote-address")
+        );
+        let preamble = count != 0;
+
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            "ipsec_peer_info",
+            "1",
+            &format!(
+                "{},id=\"{}\",state=\"{}\",side=\"{}\",dynamic_address=\"{}\"",
+                labels,
+                label_property(peer, "id"),
+                label_property(peer, "state"),
+                label_property(peer, "side"),
+                label_property(peer, "dynamic-address")
+            ),
+            "gauge",
+            "IPsec active peer details",
+            "",
+            preamble,
+        ));
+
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            "ipsec_peer_established",
+            if peer["state"].as_str() == Some("established") {
+                "1"
+            } else {
+                "0"
+            },
+            &labels,
+            "gauge",
+            "IPsec peer is established",
+            "Boolean",
+            preamble,
+        ));
+
+        if let Some(uptime) = peer["uptime"].as_str().and_then(parse_duration) {
+            ret.push_str(&metrics(
+                &config.metrics_prefix,
+                "ipsec_peer_uptime_seconds",
+                &uptime.to_string(),
+                &labels,
+                "gauge",
+                "Time since the IPsec peer was established",
+                "seconds",
+                uptimes != 0,
+            ));
+            uptimes += 1;
+        }
+
+        for (metric, help, unit) in [
+            ("rx-bytes", "Bytes received from the IPsec peer", "bytes"),
+            ("tx-bytes", "Bytes sent to the IPsec peer", "bytes"),
+            (
+                "rx-packets",
+                "Packets received from the IPsec peer",
+                "packets",
+            ),
+            ("tx-packets", "Packets sent to the IPsec peer", "packets"),
+        ] {
+            if let Some(value) = peer[metric].as_str() {
+                ret.push_str(&metrics(
+                    &config.metrics_prefix,
+                    &format!("ipsec_peer_{}_total", metric),
+                    value,
+                    &labels,
+                    "counter",
+                    help,
+                    unit,
+                    preamble,
+                ));
+            }
+        }
+    }
+
+    // Count of SAs with an expiry
+    let mut expiries = 0;
+    for (count, sa) in sas.as_array().into_iter().flatten().enumerate() {
+        let labels = format!(
+            "spi=\"{}\",src_address=\"{}\",dst_address=\"{}\"",
+            label_property(sa, "spi"),
+            label_property(sa, "src-address"),
+            label_property(sa, "dst-address")
+        );
+        let preamble = count != 0;
+
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            "ipsec_sa_info",
+            "1",
+            &format!(
+                "{},state=\"{}\",auth_algorithm=\"{}\",enc_algorithm=\"{}\",enc_key_size=\"{}\"",
+                labels,
+                label_property(sa, "state"),
+                label_property(sa, "auth-algorithm"),
+                label_property(sa, "enc-algorithm"),
+                label_property(sa, "enc-key-size")
+            ),
+            "gauge",
+            "IPsec installed SA details",
+            "",
+            preamble,
+        ));
+
+        for (metric, name, help, unit) in [
+            (
+                "current-bytes",
+                "ipsec_sa_bytes_total",
+                "Bytes through the IPsec SA",
+                "bytes",
+            ),
+            (
+                "current-packets",
+                "ipsec_sa_packets_total",
+                "Packets through the IPsec SA",
+                "packets",
+            ),
+        ] {
+            if let Some(value) = sa[metric].as_str() {
+                ret.push_str(&metrics(
+                    &config.metrics_prefix,
+                    name,
+                    value,
+                    &labels,
+                    "counter",
+                    help,
+                    unit,
+                    preamble,
+                ));
+            }
+        }
+
+        // add-lifetime is soft/hard, e.g. 24m/30m, and expires-in is what's left of it
+        let lifetimes: Vec<Option<f64>> = sa["add-lifetime"]
+            .as_str()
+            .unwrap_or("")
+            .split('/')
+            .map(parse_duration)
+            .collect();
+        if let [Some(soft), Some(hard)] = lifetimes[..] {
+            for (i, (kind, value)) in [("soft", soft), ("hard", hard)].iter().enumerate() {
+                ret.push_str(&metrics(
+                    &config.metrics_prefix,
+                    "ipsec_sa_add_lifetime_seconds",
+                    &value.to_string(),
+                    &format!("{},kind=\"{}\"", labels, kind),
+                    "gauge",
+                    "IPsec SA lifetime",
+                    "seconds",
+                    preamble || i != 0,
+                ));
+            }
+        }
+        if let Some(expires) = sa["expires-in"].as_str().and_then(parse_duration) {
+            ret.push_str(&metrics(
+                &config.metrics_prefix,
+                "ipsec_sa_expires_in_seconds",
+                &expires.to_string(),
+                &labels,
+                "gauge",
+                "Time until the IPsec SA's lifetime is reached",
+                "seconds",
+                expiries != 0,
+            ));
+            expiries += 1;
+        }
+    }
+
+    // Error counters, the statistics are a single object, e.g. {"in-state-mismatch": "0", ...}
+    let statistics = match statistics.as_array() {
+        Some(s) => s.first().cloned().unwrap_or_default(),
+        None => statistics,
+    };
+    for (i, (counter, value)) in statistics.as_object().into_iter().flatten().enumerate() {
+        if let Some(value) = value.as_str() {
+            ret.push_str(&metrics(
+                &config.metrics_prefix,
+                "ipsec_errors_total",
+                value,
+                &format!("error=\"{}\"", escape_label_value(counter)),
+                "counter",
+                "IPsec errors from /ip/ipsec/statistics",
+                "",
+                i != 0,
+            ));
+        }
+    }
+
+    Ok(ret)
+}
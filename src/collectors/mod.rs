@@ -1,9 +1,17 @@
+pub mod bgp;
+pub mod capsman;
+pub mod dhcp;
+pub mod firewall;
+pub mod firewall_address_list;
 mod helpers;
 pub mod interfaces;
 pub mod interfaces_monitor;
 pub mod interfaces_poe;
-pub mod ip_firewall_filter;
-pub mod ip_firewall_mangle;
-pub mod ip_firewall_nat;
-pub mod ip_firewall_raw;
-pub mod system_health;
\ No newline at end of file
+pub mod ipsec;
+pub mod ospf;
+pub mod ppp;
+pub mod queue;
+pub mod route;
+pub mod system_health;
+pub mod wireguard;
+pub mod wireless;
@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+
+use crate::collectors::helpers::{
+    collector_request_get, label_property, metrics, parse_duration, router_url, state_set,
+};
+
+// Neighbor states, in the order an adjacency comes up
+const NEIGHBOR_STATES: [&str; 7] = [
+    "down", "init", "2-way", "exstart", "exchange", "loading", "full",
+];
+
+// /routing/ospf/neighbor and /routing/ospf/lsa, RouterOS 7
+pub async fn run(
+    username: String,
+    password: String,
+    address: String,
+    port: u16,
+    check_ssl: bool,
+    config: crate::configuration::Settings,
+) -> Result<String, reqwest::Error> {
+    let mut ret = "".to_owned();
+
+    // Perform requests, the LSA database can be large so only ask for what's counted
+    let neighbors = collector_request_get(
+        router_url(
+            &username,
+            &password,
+            &address,
+            port,
+            "rest/routing/ospf/neighbor",
+        ),
+        check_ssl,
+    )
+    .await?;
+    let lsas = collector_request_get(
+        router_url(
+            &username,
+            &password,
+            &address,
+            port,
+            "rest/routing/ospf/lsa?.proplist=instance,area,type",
+        ),
+        check_ssl,
+    )
+    .await?;
+
+    // Count of neighbors with an adjacency
+    let mut adjacencies = 0;
+    for (count, neighbor) in neighbors.as_array().into_iter().flatten().enumerate() {
+        let labels = format!(
+            "instance=\"{}\",area=\"{}\",interface=\"{}\",router_id=\"{}\",address=\"{}\"",
+            label_property(neighbor, "instance"),
+            label_property(neighbor, "area"),
+            label_property(neighbor, "interface"),
+            label_property(neighbor, "router-id"),
+            label_property(neighbor, "address")
+        );
+        let preamble = count != 0;
+
+        // RouterOS 7 capitalises states, e.g. Full
+        if let Some(current) = neighbor["state"].as_str() {
+            ret.push_str(&state_set(
+                &config.metrics_prefix,
+                "ospf_neighbor_state",
+                &labels,
+                "OSPF neighbor state",
+                &NEIGHBOR_STATES,
+                &current.to_lowercase(),
+                preamble,
+            ));
+        }
+
+        if let Some(changes) = neighbor["state-changes"].as_str() {
+            ret.push_str(&metrics(
+                &config.metrics_prefix,
+                "ospf_neighbor_state_changes_total",
+                changes,
+                &labels,
+                "counter",
+                "OSPF neighbor state changes",
+                "",
+                preamble,
+            ));
+        }
+
+        // Time since the adjacency came up
+        if let Some(adjacency) = neighbor["adjacency"].as_str().and_then(parse_duration) {
+            ret.push_str(&metrics(
+                &config.metrics_prefix,
+                "ospf_neighbor_adjacency_seconds",
+                &adjacency.to_string(),
+                &labels,
+                "gauge",
+                "Time since the OSPF adjacency was formed",
+                "seconds",
+                adjacencies != 0,
+            ));
+            adjacencies += 1;
+        }
+    }
+
+    // (instance, area, type) => LSAs
+    let mut lsa_counts: BTreeMap<(String, String, String), u64> = BTreeMap::new();
+    for lsa in lsas.as_array().into_iter().flatten() {
+        *lsa_counts
+            .entry((
+                label_property(lsa, "instance"),
+                label_property(lsa, "area"),
+                label_property(lsa, "type"),
+            ))
+            .or_insert(0) += 1;
+    }
+    for (i, ((instance, area, lsa_type), lsas)) in lsa_counts.iter().enumerate() {
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            "ospf_lsas",
+            &lsas.to_string(),
+            &format!(
+                "instance=\"{}\",area=\"{}\",type=\"{}\"",
+                instance, area, lsa_type
+            ),
+            "gauge",
+            "OSPF LSAs in the database, per area and type",
+            "lsas",
+            i != 0,
+        ));
+    }
+
+    Ok(ret)
+}
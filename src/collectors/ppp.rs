@@ -0,0 +1,156 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::collectors::helpers::{
+    collector_request_get, escape_label_value, label_property, metrics, parse_duration, router_url,
+};
+
+// /ppp/active and /ppp/secret
+pub async fn run(
+    username: String,
+    password: String,
+    address: String,
+    port: u16,
+    check_ssl: bool,
+    config: crate::configuration::Settings,
+) -> Result<String, reqwest::Error> {
+    let mut ret = "".to_owned();
+
+    // Perform requests, BRAS routers can have thousands of sessions so only ask for what's used
+    let active = collector_request_get(
+        router_url(
+            &username,
+            &password,
+            &address,
+            port,
+            "rest/ppp/active?.proplist=.id,name,service,caller-id,address,uptime,encoding",
+        ),
+        check_ssl,
+    )
+    .await?;
+    let secrets = collector_request_get(
+        router_url(
+            &username,
+            &password,
+            &address,
+            port,
+            "rest/ppp/secret?.proplist=name,service,profile,disabled",
+        ),
+        check_ssl,
+    )
+    .await?;
+
+    // The profile isn't on the session, so it comes from the secret. Sessions authenticated
+    // by RADIUS have no secret, and no profile
+    let secrets = secrets.as_array().cloned().unwrap_or_default();
+    let profiles: HashMap<String, String> = secrets
+        .iter()
+        .map(|s| (label_property(s, "name"), label_property(s, "profile")))
+        .collect();
+
+    // (service, profile) => sessions
+    let sessions = active.as_array().cloned().unwrap_or_default();
+    let mut counts: BTreeMap<(String, String), u64> = BTreeMap::new();
+    for session in &sessions {
+        let profile = profiles
+            .get(&label_property(session, "name"))
+            .cloned()
+            .unwrap_or_default();
+        *counts
+            .entry((label_property(session, "service"), profile))
+            .or_insert(0) += 1;
+    }
+    for (i, ((service, profile), value)) in counts.iter().enumerate() {
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            "ppp_active_sessions",
+            &value.to_string(),
+            &format!("service=\"{}\",profile=\"{}\"", service, profile),
+            "gauge",
+            "Active PPP sessions per service and profile",
+            "sessions",
+            i != 0,
+        ));
+    }
+
+    // (service, profile, disabled) => secrets
+    let mut secret_counts: BTreeMap<(String, String, String), u64> = BTreeMap::new();
+    for secret in &secrets {
+        *secret_counts
+            .entry((
+                label_property(secret, "service"),
+                label_property(secret, "profile"),
+                label_property(secret, "disabled"),
+            ))
+            .or_insert(0) += 1;
+    }
+    for (i, ((service, profile, disabled), value)) in secret_counts.iter().enumerate() {
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            "ppp_secrets",
+            &value.to_string(),
+            &format!(
+                "service=\"{}\",profile=\"{}\",disabled=\"{}\"",
+                service, profile, disabled
+            ),
+            "gauge",
+            "PPP secrets per service and profile",
+            "secrets",
+            i != 0,
+        ));
+    }
+
+    // Per session metrics, only if enabled and there aren't too many sessions
+    if !config.ppp.sessions.export(sessions.len()) {
+        return Ok(ret);
+    }
+    let mut uptimes = 0;
+    for (i, session) in sessions.iter().enumerate() {
+        // A secret can allow several concurrent sessions, the session id tells them apart
+        let labels = format!(
+            "name=\"{}\",service=\"{}\",id=\"{}\"",
+            label_property(session, "name"),
+            label_property(session, "service"),
+            label_property(session, ".id")
+        );
+
+        // The session's dynamic interface, e.g. <pppoe-user>, to join with the interface metrics
+        let interface = escape_label_value(&format!(
+            "<{}-{}>",
+            session["service"].as_str().unwrap_or(""),
+            session["name"].as_str().unwrap_or("")
+        ));
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            "ppp_session_info",
+            "1",
+            &format!(
+                "{},interface=\"{}\",caller_id=\"{}\",address=\"{}\",encoding=\"{}\"",
+                labels,
+                interface,
+                label_property(session, "caller-id"),
+                label_property(session, "address"),
+                label_property(session, "encoding")
+            ),
+            "gauge",
+            "Active PPP session",
+            "",
+            i != 0,
+        ));
+
+        if let Some(uptime) = session["uptime"].as_str().and_then(parse_duration) {
+            ret.push_str(&metrics(
+                &config.metrics_prefix,
+                "ppp_session_uptime_seconds",
+                &uptime.to_string(),
+                &labels,
+                "gauge",
+                "Time since the PPP session was established",
+                "seconds",
+                uptimes != 0,
+            ));
+            uptimes += 1;
+        }
+    }
+
+    Ok(ret)
+}
@@ -0,0 +1,170 @@
+use std::fmt;
+
+use crate::collectors::helpers::{
+    collector_request_get, label_property, metrics, parse_rate, router_url,
+};
+
+// Queues under /queue
+#[derive(Clone, Copy, Debug)]
+pub enum Kind {
+    Simple,
+    Tree,
+}
+
+impl Kind {
+    pub const ALL: [Kind; 2] = [Kind::Simple, Kind::Tree];
+
+    // Name of the collector that enables this kind of queue, e.g. queue_tree
+    pub fn collector(&self) -> String {
+        format!("queue_{}", self)
+    }
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Kind::Simple => write!(f, "simple"),
+            Kind::Tree => write!(f, "tree"),
+        }
+    }
+}
+
+// Properties exported per queue, as (property, metric, help, unit, type)
+const QUEUE_METRICS: [(&str, &str, &str, &str, &str); 9] = [
+    (
+        "bytes",
+        "bytes_total",
+        "Bytes through the queue",
+        "bytes",
+        "counter",
+    ),
+    (
+        "packets",
+        "packets_total",
+        "Packets through the queue",
+        "packets",
+        "counter",
+    ),
+    (
+        "dropped",
+        "dropped_total",
+        "Packets dropped by the queue",
+        "packets",
+        "counter",
+    ),
+    (
+        "queued-bytes",
+        "queued_bytes",
+        "Bytes waiting in the queue",
+        "bytes",
+        "gauge",
+    ),
+    (
+        "queued-packets",
+        "queued_packets",
+        "Packets waiting in the queue",
+        "packets",
+        "gauge",
+    ),
+    (
+        "rate",
+        "rate",
+        "Current rate through the queue",
+        "bps",
+        "gauge",
+    ),
+    (
+        "packet-rate",
+        "packet_rate",
+        "Current packet rate through the queue",
+        "pps",
+        "gauge",
+    ),
+    (
+        "max-limit",
+        "max_limit",
+        "Maximum rate of the queue, 0 if unlimited",
+        "bps",
+        "gauge",
+    ),
+    (
+        "limit-at",
+        "limit_at",
+        "Guaranteed rate of the queue",
+        "bps",
+        "gauge",
+    ),
+];
+
+// /queue/simple or /queue/tree
+pub async fn run(
+    kind: Kind,
+    username: String,
+    password: String,
+    address: String,
+    port: u16,
+    check_ssl: bool,
+    config: crate::configuration::Settings,
+) -> Result<String, reqwest::Error> {
+    let mut ret = "".to_owned();
+
+    // Perform request
+    let json = collector_request_get(
+        router_url(
+            &username,
+            &password,
+            &address,
+            port,
+            &format!("rest/queue/{}", kind),
+        ),
+        check_ssl,
+    )
+    .await?;
+
+    let queues = match json.as_array() {
+        Some(queues) => queues,
+        None => return Ok(ret),
+    };
+
+    // Each metric in turn, so its series are together
+    for (property, metric, help, unit, metric_type) in QUEUE_METRICS {
+        let mut count = 0;
+        for queue in queues {
+            let value = match queue[property].as_str() {
+                Some(value) => value,
+                None => continue,
+            };
+            let labels = format!(
+                "queue=\"{}\",parent=\"{}\"",
+                label_property(queue, "name"),
+                label_property(queue, "parent")
+            );
+
+            // Simple queues are upload/download pairs, e.g. 10M/20M
+            let values: Vec<(String, &str)> = match (kind, value.split_once('/')) {
+                (Kind::Simple, Some((upload, download))) => vec![
+                    (format!("{},direction=\"upload\"", labels), upload),
+                    (format!("{},direction=\"download\"", labels), download),
+                ],
+                _ => vec![(labels, value)],
+            };
+            for (labels, value) in values {
+                if let Some(value) = parse_rate(value) {
+                    ret.push_str(&metrics(
+                        &config.metrics_prefix,
+                        &format!("queue_{}_{}", kind, metric),
+                        &value.to_string(),
+                        &labels,
+                        metric_type,
+                        help,
+                        unit,
+                        count != 0,
+                    ));
+                    count += 1;
+                }
+            }
+        }
+    }
+
+    Ok(ret)
+}
@@ -0,0 +1,158 @@
+use crate::collectors::firewall::Family;
+use crate::collectors::helpers::{
+    collector_client, collector_request_count, collector_request_get, escape_label_value, metrics,
+    router_url,
+};
+
+// Route flags counted per table when active, and the protocol label they're exported as
+const PROTOCOLS: [(&str, &str); 5] = [
+    ("connect", "connected"),
+    ("static", "static"),
+    ("bgp", "bgp"),
+    ("ospf", "ospf"),
+    ("dhcp", "dhcp"),
+];
+
+// Summary of /ip/route or /ipv6/route per routing table, RouterOS 7
+//
+// Full tables are ~1M routes, so nothing is downloaded per route, every figure is a
+// print count-only with the conditions filtered on the router
+pub async fn run(
+    family: Family,
+    username: String,
+    password: String,
+    address: String,
+    port: u16,
+    check_ssl: bool,
+    config: crate::configuration::Settings,
+) -> Result<String, reqwest::Error> {
+    let mut ret = "".to_owned();
+
+    // Routing tables, VRFs have a table of the same name. main is dynamic and may not be listed
+    let json = collector_request_get(
+        router_url(
+            &username,
+            &password,
+            &address,
+            port,
+            "rest/routing/table?.proplist=name",
+        ),
+        check_ssl,
+    )
+    .await?;
+    let mut tables = vec!["main".to_string()];
+    for table in json.as_array().into_iter().flatten() {
+        if let Some(name) = table["name"].as_str() {
+            if !tables.iter().any(|t| t == name) {
+                tables.push(name.to_string());
+            }
+        }
+    }
+
+    let default_route = match family {
+        Family::Ip => "0.0.0.0/0",
+        Family::Ipv6 => "::/0",
+    };
+
+    // (metric, table, protocol, conditions) for each count
+    let mut counts = vec![];
+    for table in &tables {
+        let in_table = format!("routing-table={}", table);
+        for (flag, protocol) in PROTOCOLS {
+            counts.push((
+                "routes_active",
+                table,
+                Some(protocol),
+                vec![
+                    in_table.clone(),
+                    "active=true".to_string(),
+                    format!("{}=true", flag),
+                ],
+            ));
+        }
+        counts.push((
+            "routes_inactive",
+            table,
+            None,
+            vec![in_table.clone(), "inactive=true".to_string()],
+        ));
+        counts.push((
+            "routes_unreachable",
+            table,
+            None,
+            vec![in_table.clone(), "unreachable=true".to_string()],
+        ));
+        counts.push((
+            "routes_ecmp",
+            table,
+            None,
+            vec![in_table.clone(), "ecmp=true".to_string()],
+        ));
+        counts.push((
+            "route_default",
+            table,
+            None,
+            vec![
+                in_table.clone(),
+                "active=true".to_string(),
+                format!("dst-address={}", default_route),
+            ],
+        ));
+    }
+
+    // Keep each metric's series together
+    counts.sort_by_key(|(metric, _, _, _)| *metric);
+
+    // Perform requests one after another over one connection, there are several per table and
+    // the router only allows a few concurrent sessions
+    let client = collector_client(check_ssl);
+    let url = router_url(
+        &username,
+        &password,
+        &address,
+        port,
+        &format!("rest/{}/route/print", family),
+    );
+
+    // Preamble on the first series of each metric
+    let mut emitted: Vec<&str> = vec![];
+    for (metric, table, protocol, query) in &counts {
+        let count = match collector_request_count(&client, url.clone(), query.clone()).await? {
+            Some(count) => count,
+            None => continue,
+        };
+        let mut labels = format!("table=\"{}\"", escape_label_value(table));
+        if let Some(protocol) = protocol {
+            labels.push_str(&format!(",protocol=\"{}\"", protocol));
+        }
+        let (value, help, unit) = match *metric {
+            "routes_active" => (count, "Active routes per table and protocol", "routes"),
+            "routes_inactive" => (count, "Inactive routes per table", "routes"),
+            "routes_unreachable" => (
+                count,
+                "Routes per table with an unreachable gateway",
+                "routes",
+            ),
+            "routes_ecmp" => (count, "ECMP routes per table", "routes"),
+            // Only whether there is one
+            _ => (
+                (count > 0) as u64,
+                "Table has an active default route",
+                "Boolean",
+            ),
+        };
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            &format!("{}_{}", family, metric),
+            &value.to_string(),
+            &labels,
+            "gauge",
+            help,
+            unit,
+            emitted.contains(metric),
+        ));
+        emitted.push(metric);
+    }
+
+    Ok(ret)
+}
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 use std::error::Error;
 
 use crate::collectors::helpers::{
-    collector_preamble, collector_request_get, make_metric, make_metric_preamble,
+    collector_preamble, collector_request_get, make_metric, make_metric_preamble, router_url,
 };
 
 pub fn metrics(
@@ -42,13 +42,10 @@ pub async fn run(
 
     // Perform request
     let json = collector_request_get(
-        format!(
-            "https://{}:{}@{}:{}/{}",
-            username, password, address, port, "rest/system/health"
-        ),
+        router_url(&username, &password, &address, port, "rest/system/health"),
         check_ssl,
     )
-        .await?;
+    .await?;
 
     if json.as_array() != None {
         for i in json.as_array().unwrap() {
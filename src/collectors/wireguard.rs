@@ -0,0 +1,114 @@
+use crate::collectors::helpers::{
+    collector_request_get, escape_label_value, label_property, metrics, parse_duration, router_url,
+};
+
+// /interface/wireguard/peers, RouterOS 7
+pub async fn run(
+    username: String,
+    password: String,
+    address: String,
+    port: u16,
+    check_ssl: bool,
+    config: crate::configuration::Settings,
+) -> Result<String, reqwest::Error> {
+    let mut ret = "".to_owned();
+
+    // Perform request
+    let json = collector_request_get(
+        router_url(
+            &username,
+            &password,
+            &address,
+            port,
+            "rest/interface/wireguard/peers",
+        ),
+        check_ssl,
+    )
+    .await?;
+
+    // Count of peers that have had a handshake
+    let mut handshakes = 0;
+    for (count, peer) in json.as_array().into_iter().flatten().enumerate() {
+        // Peers are keyed by their public key, which is unique per interface
+        let labels = format!(
+            "interface=\"{}\",public_key=\"{}\"",
+            label_property(peer, "interface"),
+            label_property(peer, "public-key")
+        );
+        let preamble = count != 0;
+
+        // The endpoint the peer is connecting from, or the configured one if it hasn't
+        let (endpoint_address, endpoint_port) = match peer["current-endpoint-address"].as_str() {
+            Some(a) if !a.is_empty() => (
+                escape_label_value(a),
+                label_property(peer, "current-endpoint-port"),
+            ),
+            _ => (
+                label_property(peer, "endpoint-address"),
+                label_property(peer, "endpoint-port"),
+            ),
+        };
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            "wireguard_peer_info",
+            "1",
+            &format!(
+                "{},name=\"{}\",comment=\"{}\",endpoint_address=\"{}\",endpoint_port=\"{}\",allowed_address=\"{}\",disabled=\"{}\"",
+                labels,
+                label_property(peer, "name"),
+                label_property(peer, "comment"),
+                endpoint_address,
+                endpoint_port,
+                label_property(peer, "allowed-address"),
+                label_property(peer, "disabled")
+            ),
+            "gauge",
+            "WireGuard peer details",
+            "",
+            preamble,
+        ));
+
+        for (metric, name, help) in [
+            (
+                "rx",
+                "wireguard_peer_rx_bytes_total",
+                "Bytes received from the WireGuard peer",
+            ),
+            (
+                "tx",
+                "wireguard_peer_tx_bytes_total",
+                "Bytes sent to the WireGuard peer",
+            ),
+        ] {
+            if let Some(value) = peer[metric].as_str() {
+                ret.push_str(&metrics(
+                    &config.metrics_prefix,
+                    name,
+                    value,
+                    &labels,
+                    "counter",
+                    help,
+                    "bytes",
+                    preamble,
+                ));
+            }
+        }
+
+        // Time since the last handshake, missing if there hasn't been one
+        if let Some(age) = peer["last-handshake"].as_str().and_then(parse_duration) {
+            ret.push_str(&metrics(
+                &config.metrics_prefix,
+                "wireguard_peer_last_handshake_seconds",
+                &age.to_string(),
+                &labels,
+                "gauge",
+                "Time since the last handshake with the WireGuard peer",
+                "seconds",
+                handshakes != 0,
+            ));
+            handshakes += 1;
+        }
+    }
+
+    Ok(ret)
+}
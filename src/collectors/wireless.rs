@@ -0,0 +1,312 @@
+use std::collections::{BTreeMap, HashMap};
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use crate::collectors::helpers::{
+    collector_request_get, collector_request_post, escape_label_value, label_property, metrics,
+    parse_duration, parse_number, parse_rate, router_url,
+};
+
+// Registration tables, for the wifi (wifiwave2) package and the legacy wireless package
+// Only one of them is normally installed, the other returns nothing
+const PACKAGES: [&str; 2] = ["wifi", "wireless"];
+
+// A metric's name, help and unit
+type Metric = (&'static str, &'static str, &'static str);
+
+// /interface/wifi/registration-table, /interface/wireless/registration-table and
+// /interface/wifi/monitor
+pub async fn run(
+    username: String,
+    password: String,
+    address: String,
+    port: u16,
+    check_ssl: bool,
+    config: crate::configuration::Settings,
+) -> Result<String, reqwest::Error> {
+    let mut ret = "".to_owned();
+
+    // Perform requests
+    let mut clients = vec![];
+    for package in PACKAGES {
+        let json = collector_request_get(
+            router_url(
+                &username,
+                &password,
+                &address,
+                port,
+                &format!("rest/interface/{}/registration-table", package),
+            ),
+            check_ssl,
+        )
+        .await?;
+        for client in json.as_array().into_iter().flatten() {
+            clients.push((package, client.clone()));
+        }
+    }
+
+    // (package, interface) => clients
+    let mut counts: BTreeMap<(&str, String), u64> = BTreeMap::new();
+    for (package, client) in &clients {
+        *counts
+            .entry((package, label_property(client, "interface")))
+            .or_insert(0) += 1;
+    }
+    for (i, ((package, interface), value)) in counts.iter().enumerate() {
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            "wireless_clients",
+            &value.to_string(),
+            &format!("interface=\"{}\",package=\"{}\"", interface, package),
+            "gauge",
+            "Registered wireless clients per interface",
+            "clients",
+            i != 0,
+        ));
+    }
+
+    ret.push_str(&wifi_monitor(&username, &password, &address, port, check_ssl, &config).await?);
+
+    // Per client metrics, only if enabled and there aren't too many clients
+    let wireless = &config.wireless;
+    if !wireless.clients.export(clients.len()) {
+        return Ok(ret);
+    }
+    // Metric => (labels, value), so each metric's series are together
+    let mut series: BTreeMap<Metric, Vec<(String, String)>> = BTreeMap::new();
+    for (package, client) in &clients {
+        let mac = client["mac-address"].as_str().unwrap_or("");
+        let mac = if wireless.anonymize_mac {
+            anonymize_mac(&wireless.mac_salt, mac)
+        } else {
+            escape_label_value(mac)
+        };
+        let labels = format!(
+            "interface=\"{}\",mac_address=\"{}\"",
+            label_property(client, "interface"),
+            mac
+        );
+
+        series
+            .entry(("wireless_client_info", "Registered wireless client", ""))
+            .or_default()
+            .push((
+                format!(
+                    "{},package=\"{}\",ssid=\"{}\",band=\"{}\"",
+                    labels,
+                    package,
+                    label_property(client, "ssid"),
+                    label_property(client, "band")
+                ),
+                "1".to_string(),
+            ));
+
+        // wifi reports signal, the legacy package signal-strength, e.g. -60@HT20-7
+        let signal = ["signal", "signal-strength"]
+            .iter()
+            .find_map(|p| client[*p].as_str().and_then(parse_number));
+        // e.g. 866.6Mbps-80MHz/2S/SGI
+        let rate = |property: &str| {
+            client[property]
+                .as_str()
+                .and_then(|r| r.split("bps").next())
+                .and_then(parse_rate)
+        };
+        for (name, help, unit, value) in [
+            (
+                "wireless_client_signal",
+                "Wireless client signal strength",
+                "dBm",
+                signal,
+            ),
+            (
+                "wireless_client_tx_rate",
+                "Wireless client transmit rate",
+                "bps",
+                rate("tx-rate"),
+            ),
+            (
+                "wireless_client_rx_rate",
+                "Wireless client receive rate",
+                "bps",
+                rate("rx-rate"),
+            ),
+            (
+                "wireless_client_uptime_seconds",
+                "Time since the wireless client registered",
+                "seconds",
+                client["uptime"].as_str().and_then(parse_duration),
+            ),
+        ] {
+            if let Some(value) = value {
+                series
+                    .entry((name, help, unit))
+                    .or_default()
+                    .push((labels.clone(), value.to_string()));
+            }
+        }
+    }
+    for ((name, help, unit), values) in series {
+        for (i, (labels, value)) in values.iter().enumerate() {
+            ret.push_str(&metrics(
+                &config.metrics_prefix,
+                name,
+                value,
+                labels,
+                "gauge",
+                help,
+                unit,
+                i != 0,
+            ));
+        }
+    }
+
+    Ok(ret)
+}
+
+// Channel, frequency and noise for each wifi interface
+async fn wifi_monitor(
+    username: &str,
+    password: &str,
+    address: &str,
+    port: u16,
+    check_ssl: bool,
+    config: &crate::configuration::Settings,
+) -> Result<String, reqwest::Error> {
+    let mut ret = "".to_owned();
+
+    let json = collector_request_get(
+        router_url(
+            username,
+            password,
+            address,
+            port,
+            "rest/interface/wifi?.proplist=.id",
+        ),
+        check_ssl,
+    )
+    .await?;
+    let interfaces: Vec<&str> = json
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|i| i[".id"].as_str())
+        .collect();
+    if interfaces.is_empty() {
+        return Ok(ret);
+    }
+
+    // Build hashmap that we'll send as JSON for next request
+    let numbers = interfaces.join(",");
+    let mut map = HashMap::new();
+    map.insert("numbers", numbers.as_str());
+    map.insert("duration", config.monitor.duration.as_str());
+    map.insert("interval", config.monitor.interval.as_str());
+
+    let json = collector_request_post(
+        router_url(
+            username,
+            password,
+            address,
+            port,
+            "rest/interface/wifi/monitor",
+        ),
+        map,
+        check_ssl,
+    )
+    .await?;
+
+    for (i, interface) in json.as_array().into_iter().flatten().enumerate() {
+        let labels = format!("interface=\"{}\"", label_property(interface, "name"));
+        let preamble = i != 0;
+
+        // Channel is frequency/band/width, e.g. 5180/ax/Ceee
+        let channel = interface["channel"].as_str().unwrap_or("");
+        ret.push_str(&metrics(
+            &config.metrics_prefix,
+            "wifi_interface_info",
+            "1",
+            &format!(
+                "{},channel=\"{}\",state=\"{}\"",
+                labels,
+                escape_label_value(channel),
+                label_property(interface, "state")
+            ),
+            "gauge",
+            "Wifi interface channel and state",
+            "",
+            preamble,
+        ));
+        for (name, help, unit, value) in [
+            (
+                "wifi_interface_frequency",
+                "Wifi interface channel frequency",
+                "MHz",
+                parse_number(channel),
+            ),
+            (
+                "wifi_interface_noise_floor",
+                "Wifi interface noise floor",
+                "dBm",
+                interface["noise-floor"].as_str().and_then(parse_number),
+            ),
+            (
+                "wifi_interface_tx_power",
+                "Wifi interface transmit power",
+                "dBm",
+                interface["tx-power"].as_str().and_then(parse_number),
+            ),
+            (
+                "wifi_interface_registered_peers",
+                "Wifi interface registered peers",
+                "clients",
+                interface["registered-peers"]
+                    .as_str()
+                    .and_then(parse_number),
+            ),
+        ] {
+            if let Some(value) = value {
+                ret.push_str(&metrics(
+                    &config.metrics_prefix,
+                    name,
+                    &value.to_string(),
+                    &labels,
+                    "gauge",
+                    help,
+                    unit,
+                    preamble,
+                ));
+            }
+        }
+    }
+
+    Ok(ret)
+}
+
+// A stable pseudonym for a MAC address, HMAC-SHA256 of the upper case address keyed with the
+// salt. MAC addresses are few enough to hash them all, so without the key being secret the
+// pseudonyms could be reversed
+fn anonymize_mac(salt: &str, mac: &str) -> String {
+    let mut hmac = Hmac::<Sha256>::new_from_slice(salt.as_bytes()).expect("HMAC takes any key");
+    hmac.update(mac.to_uppercase().as_bytes());
+    hmac.finalize().into_bytes()[..16]
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn anonymized_macs() {
+        let mac = anonymize_mac("secret", "aa:bb:cc:11:22:33");
+        assert_eq!(mac.len(), 32);
+        assert_eq!(mac, anonymize_mac("secret", "AA:BB:CC:11:22:33"));
+        assert_ne!(mac, anonymize_mac("other", "AA:BB:CC:11:22:33"));
+        assert_ne!(mac, anonymize_mac("secret", "AA:BB:CC:11:22:34"));
+        assert_eq!(mac, "31169a6b493f7fe48167d69fdab1c447");
+    }
+}
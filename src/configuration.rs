@@ -1,57 +1,477 @@
+use std::collections::HashMap;
+
+use regex::Regex;
 use secrecy::Secret;
 use serde_aux::field_attributes::{deserialize_bool_from_anything, deserialize_number_from_string};
 
+use crate::collectors::firewall::{Family, Table};
+
 #[derive(serde::Deserialize, Clone)]
 pub struct Server {
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub port: u16,
 }
 
+// Per entry metrics for a table that can be very large, e.g. [firewall.address_list]
 #[derive(serde::Deserialize, Clone)]
-pub struct Collectors {
+#[serde(default)]
+pub struct Entries {
+    // Export a metric per entry, not just the counts
+    #[serde(deserialize_with = "deserialize_bool_from_anything")]
+    pub entries: bool,
+    // Per entry metrics are skipped if there are more entries than this
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_entries: usize,
+}
+
+impl Default for Entries {
+    fn default() -> Self {
+        Entries {
+            entries: false,
+            max_entries: 1000,
+        }
+    }
+}
+
+impl Entries {
+    // Whether to export per entry metrics when there are `count` entries
+    pub fn export(&self, count: usize) -> bool {
+        self.entries && count <= self.max_entries
+    }
+}
+
+// [firewall]
+#[derive(serde::Deserialize, Clone)]
+#[serde(default)]
+pub struct Firewall {
+    // Rule properties (e.g. action, src-address) promoted to labels on the packet and byte
+    // counters, in addition to the rule id, chain and position
+    pub labels: Vec<String>,
+    // Property values in the rule info metric are truncated to this many characters
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub max_label_length: usize,
+    // [firewall.address_list]
+    pub address_list: Entries,
+}
+
+impl Default for Firewall {
+    fn default() -> Self {
+        Firewall {
+            labels: vec![],
+            max_label_length: 128,
+            address_list: Entries::default(),
+        }
+    }
+}
+
+// [dhcp]
+#[derive(serde::Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Dhcp {
+    // [dhcp.leases]
+    pub leases: Entries,
+}
+
+// [ppp]
+#[derive(serde::Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Ppp {
+    // [ppp.sessions]
+    pub sessions: Entries,
+}
+
+// [wireless]
+#[derive(serde::Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct Wireless {
+    // [wireless.clients]
+    pub clients: Entries,
+    // Replace client MAC addresses with a hash of the salt and MAC address
+    #[serde(deserialize_with = "deserialize_bool_from_anything")]
+    pub anonymize_mac: bool,
+    pub mac_salt: String,
+}
+
+// [monitor]
+#[derive(serde::Deserialize, Clone)]
+#[serde(default)]
+pub struct Monitor {
+    // How long /interface/ethernet/monitor runs for, and how often it samples, as RouterOS
+    // durations, e.g. 0.1s
+    pub duration: String,
+    pub interval: String,
+}
+
+impl Default for Monitor {
+    fn default() -> Self {
+        Monitor {
+            duration: "0.1s".to_string(),
+            interval: "0.1s".to_string(),
+        }
+    }
+}
+
+// Declares Collectors with a flag per collector, along with Collectors::NAMES and
+// Collectors::get_mut, so a collector can't be added to one and missed in the others
+macro_rules! collectors {
+    ($($name:ident,)*) => {
+        #[derive(serde::Deserialize, serde::Serialize, Clone, Default)]
+        pub struct Collectors {
+            $(pub $name: Option<bool>,)*
+        }
+
+        impl Collectors {
+            // Every collector name that can be configured
+            pub const NAMES: &'static [&'static str] = &[$(stringify!($name),)*];
+
+            // Flag for a collector by name, None if there's no such collector
+            pub fn get_mut(&mut self, name: &str) -> Option<&mut Option<bool>> {
+                match name {
+                    $(stringify!($name) => Some(&mut self.$name),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+collectors! {
     // /ip/firewall/
     //// ip_firewall enables collection, each sub command needs to also be enabled
-    pub ip_firewall: Option<bool>,
-    pub ip_firewall_filter: Option<bool>,
-    pub ip_firewall_nat: Option<bool>,
-    pub ip_firewall_mangle: Option<bool>,
-    pub ip_firewall_raw: Option<bool>,
-    pub ip_firewall_conntrack: Option<bool>,
+    ip_firewall,
+    ip_firewall_filter,
+    ip_firewall_nat,
+    ip_firewall_mangle,
+    ip_firewall_raw,
+    ip_firewall_address_list,
+    ip_firewall_conntrack,
+
+    // /ipv6/firewall/
+    //// ipv6_firewall enables collection, each sub command needs to also be enabled
+    ipv6_firewall,
+    ipv6_firewall_filter,
+    ipv6_firewall_nat,
+    ipv6_firewall_mangle,
+    ipv6_firewall_raw,
+    ipv6_firewall_address_list,
 
     // /system/health/
-    pub health: Option<bool>,
+    health,
 
     // /system/resources/
-    pub resources: Option<bool>,
+    resources,
 
     // /interfaces
-    pub interfaces: Option<bool>,
+    interfaces,
     // /interfaces/poe
-    pub interfaces_poe: Option<bool>,
+    interfaces_poe,
     // /interfaces/ethernet/monitor
-    pub interfaces_monitor: Option<bool>,
+    interfaces_monitor,
+
+    // /routing/bgp/
+    bgp,
+
+    // /routing/ospf/
+    ospf,
+
+    // /ip/route/ and /ipv6/route/
+    ip_route,
+    ipv6_route,
+
+    // /ip/dhcp-server/ and /ip/pool/
+    dhcp,
+
+    // /interface/wireguard/peers/
+    wireguard,
+
+    // /ip/ipsec/
+    ipsec,
+
+    // /ppp/
+    ppp,
+
+    // /queue/simple/ and /queue/tree/
+    queue_simple,
+    queue_tree,
+
+    // /interface/wifi/ and /interface/wireless/
+    wireless,
+
+    // /caps-man/ and /interface/wifi/capsman/
+    capsman,
+}
+
+impl Collectors {
+    // Collectors that can be configured but have nothing behind them yet. They are never run,
+    // and requesting one in ?collectors= is rejected
+    pub const NOT_IMPLEMENTED: &'static [&'static str] = &["ip_firewall_conntrack", "resources"];
+
+    // Flag for a collector by name, None if unset or there's no such collector
+    pub fn get(&self, name: &str) -> Option<bool> {
+        self.clone().get_mut(name).and_then(|v| *v)
+    }
+
+    // Whether a collector runs for a probe. A family's firewall tables and address lists only run
+    // if the family is also enabled, e.g. ip_firewall_filter needs ip_firewall, and collectors
+    // that aren't implemented never run
+    pub fn enabled(&self, name: &str) -> bool {
+        if Collectors::NOT_IMPLEMENTED.contains(&name) {
+            return false;
+        }
+        for family in Family::ALL {
+            let mut children = Table::ALL
+                .iter()
+                .map(|t| t.collector(family))
+                .chain([family.address_list_collector()]);
+            if children.any(|c| c == name) && self.get(&family.collector()) != Some(true) {
+                return false;
+            }
+        }
+        self.get(name) == Some(true)
+    }
+
+    // Layer `over` on top of self, fields that aren't set in `over` are inherited
+    pub fn merge(&self, over: &Collectors) -> Collectors {
+        let mut merged = self.clone();
+        for name in Collectors::NAMES {
+            if let Some(v) = over.get(name) {
+                *merged.get_mut(name).unwrap() = Some(v);
+            }
+        }
+        merged
+    }
+}
+
+// Which interfaces the interface collectors export. Patterns are regular expressions that
+// must match the whole name, type or comment
+#[derive(serde::Deserialize, serde::Serialize, Clone, Default)]
+pub struct InterfaceFilter {
+    pub include_name: Option<String>,
+    pub exclude_name: Option<String>,
+    pub include_type: Option<String>,
+    pub exclude_type: Option<String>,
+    pub include_comment: Option<String>,
+    pub exclude_comment: Option<String>,
+    // Dynamic interfaces, e.g. PPP sessions, are excluded unless this is set
+    pub include_dynamic: Option<bool>,
+}
+
+impl InterfaceFilter {
+    // Layer `over` on top of self, fields that aren't set in `over` are inherited
+    pub fn merge(&self, over: &InterfaceFilter) -> InterfaceFilter {
+        InterfaceFilter {
+            include_name: over
+                .include_name
+                .clone()
+                .or_else(|| self.include_name.clone()),
+            exclude_name: over
+                .exclude_name
+                .clone()
+                .or_else(|| self.exclude_name.clone()),
+            include_type: over
+                .include_type
+                .clone()
+                .or_else(|| self.include_type.clone()),
+            exclude_type: over
+                .exclude_type
+                .clone()
+                .or_else(|| self.exclude_type.clone()),
+            include_comment: over
+                .include_comment
+                .clone()
+                .or_else(|| self.include_comment.clone()),
+            exclude_comment: over
+                .exclude_comment
+                .clone()
+                .or_else(|| self.exclude_comment.clone()),
+            include_dynamic: over.include_dynamic.or(self.include_dynamic),
+        }
+    }
+
+    pub fn matcher(&self) -> Result<InterfaceMatcher, regex::Error> {
+        let compile = |pattern: &Option<String>| -> Result<Option<Regex>, regex::Error> {
+            match pattern {
+                Some(p) => Ok(Some(Regex::new(&format!("^(?:{})$", p))?)),
+                None => Ok(None),
+            }
+        };
+        Ok(InterfaceMatcher {
+            include_name: compile(&self.include_name)?,
+            exclude_name: compile(&self.exclude_name)?,
+            include_type: compile(&self.include_type)?,
+            exclude_type: compile(&self.exclude_type)?,
+            include_comment: compile(&self.include_comment)?,
+            exclude_comment: compile(&self.exclude_comment)?,
+            include_dynamic: self.include_dynamic.unwrap_or(false),
+        })
+    }
+}
+
+// Compiled InterfaceFilter, passed to the interface collectors
+#[derive(Clone, Default)]
+pub struct InterfaceMatcher {
+    include_name: Option<Regex>,
+    exclude_name: Option<Regex>,
+    include_type: Option<Regex>,
+    exclude_type: Option<Regex>,
+    include_comment: Option<Regex>,
+    exclude_comment: Option<Regex>,
+    include_dynamic: bool,
+}
+
+impl InterfaceMatcher {
+    pub fn matches(&self, name: &str, interface_type: &str, comment: &str, dynamic: bool) -> bool {
+        let included = |re: &Option<Regex>, v: &str| re.as_ref().is_none_or(|re| re.is_match(v));
+        let excluded = |re: &Option<Regex>, v: &str| re.as_ref().is_some_and(|re| re.is_match(v));
+
+        (self.include_dynamic || !dynamic)
+            && included(&self.include_name, name)
+            && !excluded(&self.exclude_name, name)
+            && included(&self.include_type, interface_type)
+            && !excluded(&self.exclude_type, interface_type)
+            && included(&self.include_comment, comment)
+            && !excluded(&self.exclude_comment, comment)
+    }
+}
+
+// [modules.<name>], a set of collectors, and optionally an interface filter
+#[derive(serde::Deserialize, Clone)]
+pub struct Module {
+    #[serde(flatten)]
+    pub collectors: Collectors,
+    pub interface_filter: Option<InterfaceFilter>,
 }
 
 #[derive(serde::Deserialize, Clone)]
 pub struct RouterConfiguration {
     pub username: String,
-    pub password: Secret<String>,
+    // One of password, password_file or password_env, see resolve_password
+    pub password: Option<Secret<String>>,
+    pub password_file: Option<String>,
+    pub password_env: Option<String>,
     #[serde(deserialize_with = "deserialize_bool_from_anything")]
     pub check_ssl: bool,
     pub address: String,
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub port: u16,
     pub collectors: Option<Collectors>,
+    pub interface_filter: Option<InterfaceFilter>,
+}
+
+// Named credentials, selected with ?auth=<name>
+#[derive(serde::Deserialize, Clone)]
+pub struct AuthProfile {
+    pub username: String,
+    pub password: Option<Secret<String>>,
+    pub password_file: Option<String>,
+    pub password_env: Option<String>,
+    #[serde(deserialize_with = "deserialize_bool_from_anything")]
+    pub check_ssl: bool,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub port: u16,
+}
+
+#[derive(Debug)]
+pub enum SecretError {
+    NotSet(String),
+    File(String, String, std::io::Error),
+    Env(String, String),
+}
+
+// Only ever names the instance and where the secret was expected, never the value
+impl std::fmt::Display for SecretError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SecretError::NotSet(instance) => write!(f, "no password configured for {}", instance),
+            SecretError::File(instance, path, e) => write!(
+                f,
+                "failed to read password_file '{}' for {}: {}",
+                path, instance, e
+            ),
+            SecretError::Env(instance, var) => {
+                write!(f, "password_env '{}' for {} is not set", var, instance)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SecretError {}
+
+// Secrets are read every time a target is probed, so a rotated file or environment
+// variable is picked up without a restart.
+// password_file takes precedence over password_env, which takes precedence over password
+fn resolve_password(
+    instance: &str,
+    password: &Option<Secret<String>>,
+    password_file: &Option<String>,
+    password_env: &Option<String>,
+) -> Result<Secret<String>, SecretError> {
+    if let Some(path) = password_file {
+        return match std::fs::read_to_string(path) {
+            // Docker and Kubernetes secrets usually end with a newline
+            Ok(p) => Ok(Secret::new(
+                p.trim_end_matches(&['\r', '\n'][..]).to_string(),
+            )),
+            Err(e) => Err(SecretError::File(instance.to_string(), path.clone(), e)),
+        };
+    }
+    if let Some(var) = password_env {
+        return match std::env::var(var) {
+            Ok(p) => Ok(Secret::new(p)),
+            Err(_) => Err(SecretError::Env(instance.to_string(), var.clone())),
+        };
+    }
+    match password {
+        Some(p) => Ok(p.clone()),
+        None => Err(SecretError::NotSet(instance.to_string())),
+    }
+}
+
+impl RouterConfiguration {
+    pub fn resolve_password(&self, instance: &str) -> Result<Secret<String>, SecretError> {
+        resolve_password(
+            instance,
+            &self.password,
+            &self.password_file,
+            &self.password_env,
+        )
+    }
+}
+
+impl AuthProfile {
+    pub fn resolve_password(&self, instance: &str) -> Result<Secret<String>, SecretError> {
+        resolve_password(
+            instance,
+            &self.password,
+            &self.password_file,
+            &self.password_env,
+        )
+    }
 }
 
 #[derive(serde::Deserialize, Clone)]
 pub struct Settings {
     pub server: Server,
     pub metrics_prefix: String,
+    #[serde(default)]
+    pub firewall: Firewall,
+    #[serde(default)]
+    pub dhcp: Dhcp,
+    #[serde(default)]
+    pub ppp: Ppp,
+    #[serde(default)]
+    pub wireless: Wireless,
+    #[serde(default)]
+    pub monitor: Monitor,
 
     pub defaults: RouterConfiguration,
 
     pub instances: Option<Vec<RouterConfiguration>>,
+
+    // [auths.<name>], selected with ?auth=<name>
+    pub auths: Option<HashMap<String, AuthProfile>>,
+    // [modules.<name>] collector sets, selected with ?module=<name>
+    pub modules: Option<HashMap<String, Module>>,
 }
 
 pub fn get_configuration() -> Result<Settings, config::ConfigError> {
@@ -65,8 +485,9 @@ pub fn get_configuration() -> Result<Settings, config::ConfigError> {
     settings.merge(config::File::with_name("default.toml").required(true))?;
 
     // read in /config/config.toml, don't fail if the file doesnt exist
-    settings
-        .merge(config::File::from(root_configuration_directory.join("config.toml")).required(false))?;
+    settings.merge(
+        config::File::from(root_configuration_directory.join("config.toml")).required(false),
+    )?;
 
     // read in config/config.toml, don't fail if the file doesnt exist
     settings
@@ -76,5 +497,212 @@ pub fn get_configuration() -> Result<Settings, config::ConfigError> {
     settings.merge(config::Environment::with_prefix("routeros").separator("__"))?;
 
     // generate the settings
-    settings.try_into()
+    let settings: Settings = settings.try_into()?;
+
+    // Without a secret salt the anonymized MAC addresses could be reversed by hashing them all
+    if settings.wireless.anonymize_mac && settings.wireless.mac_salt.is_empty() {
+        return Err(config::ConfigError::Message(
+            "wireless.anonymize_mac needs a wireless.mac_salt".to_string(),
+        ));
+    }
+
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn collectors(enabled: &[&str]) -> Collectors {
+        let mut c = Collectors::default();
+        for name in enabled {
+            *c.get_mut(name).unwrap() = Some(true);
+        }
+        c
+    }
+
+    #[test]
+    fn every_name_has_a_flag() {
+        let mut c = Collectors::default();
+        for name in Collectors::NAMES {
+            assert!(c.get_mut(name).is_some(), "{}", name);
+        }
+        assert!(c.get_mut("nope").is_none());
+    }
+
+    #[test]
+    fn enabled_needs_the_family() {
+        let c = collectors(&["ip_firewall_filter", "ipv6_firewall", "ipv6_firewall_nat"]);
+        assert!(!c.enabled("ip_firewall_filter"));
+        assert!(c.enabled("ipv6_firewall_nat"));
+        assert!(!c.enabled("ipv6_firewall_raw"));
+
+        let c = collectors(&["ip_firewall_address_list", "ip_route"]);
+        assert!(!c.enabled("ip_firewall_address_list"));
+        assert!(c.enabled("ip_route"));
+    }
+
+    #[test]
+    fn not_implemented_never_run() {
+        let c = collectors(&["resources", "ip_firewall", "ip_firewall_conntrack"]);
+        assert!(!c.enabled("resources"));
+        assert!(!c.enabled("ip_firewall_conntrack"));
+    }
+
+    #[test]
+    fn merge_inherits_unset() {
+        let over = Collectors {
+            health: Some(false),
+            bgp: Some(true),
+            ..Default::default()
+        };
+        let merged = collectors(&["health", "interfaces"]).merge(&over);
+        assert_eq!(merged.health, Some(false));
+        assert_eq!(merged.interfaces, Some(true));
+        assert_eq!(merged.bgp, Some(true));
+        assert_eq!(merged.ospf, None);
+    }
+
+    fn filter(include_name: &str, exclude_name: &str) -> InterfaceFilter {
+        let pattern = |p: &str| Some(p.to_string()).filter(|p| !p.is_empty());
+        InterfaceFilter {
+            include_name: pattern(include_name),
+            exclude_name: pattern(exclude_name),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn matcher_anchors_whole_values() {
+        let m = filter("ether1", "").matcher().unwrap();
+        assert!(m.matches("ether1", "ether", "", false));
+        assert!(!m.matches("ether10", "ether", "", false));
+        assert!(!m.matches("vlan-ether1", "vlan", "", false));
+
+        // Alternations are anchored as a whole
+        let m = filter("ether1|sfp.*", "").matcher().unwrap();
+        assert!(m.matches("sfp-sfpplus1", "ether", "", false));
+        assert!(!m.matches("ether1-uplink", "ether", "", false));
+    }
+
+    #[test]
+    fn matcher_exclude_wins() {
+        let m = filter("ether.*", "ether2").matcher().unwrap();
+        assert!(m.matches("ether1", "ether", "", false));
+        assert!(!m.matches("ether2", "ether", "", false));
+        assert!(!m.matches("bridge", "bridge", "", false));
+
+        let m = InterfaceFilter {
+            exclude_type: Some("vlan".to_string()),
+            include_comment: Some("uplink.*".to_string()),
+            ..Default::default()
+        }
+        .matcher()
+        .unwrap();
+        assert!(m.matches("ether1", "ether", "uplink isp1", false));
+        assert!(!m.matches("vlan10", "vlan", "uplink isp1", false));
+        assert!(!m.matches("ether2", "ether", "", false));
+    }
+
+    #[test]
+    fn matcher_excludes_dynamic_by_default() {
+        let m = InterfaceFilter::default().matcher().unwrap();
+        assert!(m.matches("ether1", "ether", "", false));
+        assert!(!m.matches("<pppoe-alice>", "pppoe-in", "", true));
+
+        let m = InterfaceFilter {
+            include_dynamic: Some(true),
+            ..Default::default()
+        }
+        .matcher()
+        .unwrap();
+        assert!(m.matches("<pppoe-alice>", "pppoe-in", "", true));
+    }
+
+    #[test]
+    fn matcher_rejects_invalid_patterns() {
+        assert!(filter("ether(", "").matcher().is_err());
+        assert!(filter("", "[").matcher().is_err());
+    }
+
+    // A file under the temporary directory, removed when dropped
+    struct TempFile(std::path::PathBuf);
+
+    impl TempFile {
+        fn new(name: &str, contents: &str) -> TempFile {
+            let path = std::env::temp_dir().join(format!(
+                "routeros_exporter_{}_{}",
+                std::process::id(),
+                name
+            ));
+            std::fs::write(&path, contents).unwrap();
+            TempFile(path)
+        }
+
+        fn path(&self) -> Option<String> {
+            Some(self.0.to_string_lossy().to_string())
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    fn secret(s: &str) -> Option<Secret<String>> {
+        Some(Secret::new(s.to_string()))
+    }
+
+    #[test]
+    fn password_file_wins() {
+        use secrecy::ExposeSecret;
+
+        let file = TempFile::new("password_file_wins", "from-file");
+        std::env::set_var("ROUTEROS_TEST_PASSWORD_FILE_WINS", "from-env");
+        let env = Some("ROUTEROS_TEST_PASSWORD_FILE_WINS".to_string());
+
+        let p = resolve_password("r1", &secret("inline"), &file.path(), &env).unwrap();
+        assert_eq!(p.expose_secret(), "from-file");
+        let p = resolve_password("r1", &secret("inline"), &None, &env).unwrap();
+        assert_eq!(p.expose_secret(), "from-env");
+        let p = resolve_password("r1", &secret("inline"), &None, &None).unwrap();
+        assert_eq!(p.expose_secret(), "inline");
+    }
+
+    #[test]
+    fn password_file_trims_line_endings() {
+        use secrecy::ExposeSecret;
+
+        for (name, contents) in [("lf", "s3cr:t@\n"), ("crlf", "s3cr:t@\r\n")] {
+            let file = TempFile::new(&format!("trims_{}", name), contents);
+            let p = resolve_password("r1", &None, &file.path(), &None).unwrap();
+            assert_eq!(p.expose_secret(), "s3cr:t@");
+        }
+        // Only line endings, other whitespace may be part of the password
+        let file = TempFile::new("trims_spaces", " pass \n");
+        let p = resolve_password("r1", &None, &file.path(), &None).unwrap();
+        assert_eq!(p.expose_secret(), " pass ");
+    }
+
+    #[test]
+    fn password_errors_name_the_instance() {
+        let missing = Some("/nonexistent/routeros_exporter/password".to_string());
+        let e = resolve_password("instance 'r1'", &secret("hunter2"), &missing, &None).unwrap_err();
+        assert!(matches!(e, SecretError::File(..)));
+        let message = e.to_string();
+        assert!(message.contains("instance 'r1'"), "{}", message);
+        assert!(!message.contains("hunter2"), "{}", message);
+
+        let unset = Some("ROUTEROS_TEST_PASSWORD_UNSET".to_string());
+        let e = resolve_password("auth 'core'", &secret("hunter2"), &None, &unset).unwrap_err();
+        assert!(matches!(e, SecretError::Env(..)));
+        let message = e.to_string();
+        assert!(message.contains("auth 'core'"), "{}", message);
+        assert!(!message.contains("hunter2"), "{}", message);
+
+        let e = resolve_password("defaults", &None, &None, &None).unwrap_err();
+        assert!(matches!(e, SecretError::NotSet(..)));
+        assert!(e.to_string().contains("defaults"));
+    }
 }
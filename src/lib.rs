@@ -1,3 +1,5 @@
 pub mod collectors;
 pub mod configuration;
 pub mod macros;
+pub mod probe;
+pub mod target;
@@ -11,7 +11,10 @@ use secrecy::ExposeSecret;
 use tokio;
 use warp::Filter;
 
-use routeros_exporter::configuration::{Collectors, get_configuration};
+use routeros_exporter::collectors::firewall::{Family, Table};
+use routeros_exporter::collectors::queue::Kind;
+use routeros_exporter::configuration::{get_configuration, Collectors};
+use routeros_exporter::probe::{resolve, ProbeError};
 
 // lazy_static the config
 lazy_static! {
@@ -24,93 +27,25 @@ async fn http_get_probe(q: Vec<(String, String)>) -> Result<impl warp::Reply, In
     // Each collector will be a new future
     let tasks = futures::stream::FuturesUnordered::new();
 
-    // Clone default config
-    let mut c = CONFIG.defaults.collectors.clone().unwrap();
-    let mut port = CONFIG.defaults.port;
-    let mut username = CONFIG.defaults.username.clone();
-    let mut password = CONFIG.defaults.password.expose_secret().clone();
-    let mut address = CONFIG.defaults.address.clone();
-    let mut check_ssl = CONFIG.defaults.check_ssl;
-
     // var for results to be added to
     let mut ret = "".to_owned();
 
-    // If target in query, set it now
-    for (k, v) in q.clone() {
-        match k.as_str() {
-            "target" => address = v.as_str().parse()?,
-            _ => {}
-        }
-    }
-
-    // If the target is defined in config, override
-    if CONFIG.instances.is_some() {
-        let i = CONFIG.instances.as_ref();
-        for instance in i.unwrap() {
-            if instance.address.as_str() == address {
-                c = instance.collectors.as_ref().unwrap().clone();
-                username = instance.username.clone();
-                password = instance.password.expose_secret().clone();
-                port = instance.port;
-                check_ssl = instance.check_ssl;
-            }
-        }
-    }
-
-    // Try and match query params
-    // not using Struct as collectors could be defined multiple times, once per collector
-    for (k, v) in q {
-        match k.as_str() {
-            "collectors" => match v.as_str() {
-                "ip_firewall" => {
-                    c.ip_firewall = Some(true);
-                }
-                "ip_firewall_filter" => {
-                    c.ip_firewall_filter = Some(true);
-                }
-                "ip_firewall_nat" => {
-                    c.ip_firewall_nat = Some(true);
-                }
-                "ip_firewall_mangle" => {
-                    c.ip_firewall_mangle = Some(true);
-                }
-                "health" => {
-                    c.health = Some(true);
-                }
-                "resources" => {
-                    c.resources = Some(true);
-                }
-                "interfaces" => {
-                    c.interfaces = Some(true);
-                }
-                "interfaces_poe" => {
-                    c.interfaces_poe = Some(true);
-                }
-                "interfaces_monitor" => {
-                    c.interfaces_monitor = Some(true);
-                }
-                _ => {}
-            },
-            "check_ssl" => {
-                check_ssl = true;
-            }
-            //  Removing as this shouldn't be over the wire
-            // "username" => {
-            //     username = v.as_str().parse()?;
-            // }
-            // "password" => {
-            //     password = v;
-            // }
-            "port" => {
-                port = v.as_str().parse().unwrap();
-            }
-            _ => {}
-        }
-    }
+    // Layer defaults, auth/module, instance and query params
+    let probe = match resolve(&CONFIG, &q) {
+        Ok(p) => p,
+        Err(e) => return Ok(http_probe_error(e)),
+    };
+    let c = probe.collectors;
+    let username = probe.username;
+    let password = probe.password.expose_secret().clone();
+    let address = probe.address;
+    let port = probe.port;
+    let check_ssl = probe.check_ssl;
 
     if c.interfaces == Some(true) {
         tasks.push(routeros_exporter::spawn_collector!(
             routeros_exporter::collectors::interfaces::run,
+            probe.interfaces.clone(),
             (*username).parse()?,
             (*password).parse()?,
             (*address).parse()?,
@@ -122,6 +57,7 @@ async fn http_get_probe(q: Vec<(String, String)>) -> Result<impl warp::Reply, In
     if c.interfaces_monitor == Some(true) {
         tasks.push(routeros_exporter::spawn_collector!(
             routeros_exporter::collectors::interfaces_monitor::run,
+            probe.interfaces.clone(),
             (*username).parse()?,
             (*password).parse()?,
             (*address).parse()?,
@@ -133,6 +69,7 @@ async fn http_get_probe(q: Vec<(String, String)>) -> Result<impl warp::Reply, In
     if c.interfaces_poe == Some(true) {
         tasks.push(routeros_exporter::spawn_collector!(
             routeros_exporter::collectors::interfaces_poe::run,
+            probe.interfaces.clone(),
             (*username).parse()?,
             (*password).parse()?,
             (*address).parse()?,
@@ -142,10 +79,18 @@ async fn http_get_probe(q: Vec<(String, String)>) -> Result<impl warp::Reply, In
         ));
     }
 
-    if c.ip_firewall == Some(true) {
-        if c.ip_firewall_filter == Some(true) {
+    // One firewall collector per family, for each of the enabled tables, the address lists and routes
+    for family in Family::ALL {
+        let tables: Vec<Table> = Table::ALL
+            .iter()
+            .filter(|t| c.enabled(&t.collector(family)))
+            .copied()
+            .collect();
+        if !tables.is_empty() {
             tasks.push(routeros_exporter::spawn_collector!(
-                routeros_exporter::collectors::ip_firewall_filter::run,
+                routeros_exporter::collectors::firewall::run,
+                family,
+                tables,
                 (*username).parse()?,
                 (*password).parse()?,
                 (*address).parse()?,
@@ -154,9 +99,10 @@ async fn http_get_probe(q: Vec<(String, String)>) -> Result<impl warp::Reply, In
                 CONFIG.clone()
             ));
         }
-        if c.ip_firewall_nat == Some(true) {
+        if c.enabled(&family.address_list_collector()) {
             tasks.push(routeros_exporter::spawn_collector!(
-                routeros_exporter::collectors::ip_firewall_nat::run,
+                routeros_exporter::collectors::firewall_address_list::run,
+                family,
                 (*username).parse()?,
                 (*password).parse()?,
                 (*address).parse()?,
@@ -165,9 +111,10 @@ async fn http_get_probe(q: Vec<(String, String)>) -> Result<impl warp::Reply, In
                 CONFIG.clone()
             ));
         }
-        if c.ip_firewall_mangle == Some(true) {
+        if c.enabled(&family.route_collector()) {
             tasks.push(routeros_exporter::spawn_collector!(
-                routeros_exporter::collectors::ip_firewall_mangle::run,
+                routeros_exporter::collectors::route::run,
+                family,
                 (*username).parse()?,
                 (*password).parse()?,
                 (*address).parse()?,
@@ -176,9 +123,98 @@ async fn http_get_probe(q: Vec<(String, String)>) -> Result<impl warp::Reply, In
                 CONFIG.clone()
             ));
         }
-        if c.ip_firewall_raw == Some(true) {
+    }
+
+    if c.health == Some(true) {
+        tasks.push(routeros_exporter::spawn_collector!(
+            routeros_exporter::collectors::system_health::run,
+            (*username).parse()?,
+            (*password).parse()?,
+            (*address).parse()?,
+            port.clone(),
+            check_ssl,
+            CONFIG.clone()
+        ));
+    }
+
+    if c.bgp == Some(true) {
+        tasks.push(routeros_exporter::spawn_collector!(
+            routeros_exporter::collectors::bgp::run,
+            (*username).parse()?,
+            (*password).parse()?,
+            (*address).parse()?,
+            port.clone(),
+            check_ssl,
+            CONFIG.clone()
+        ));
+    }
+
+    if c.ospf == Some(true) {
+        tasks.push(routeros_exporter::spawn_collector!(
+            routeros_exporter::collectors::ospf::run,
+            (*username).parse()?,
+            (*password).parse()?,
+            (*address).parse()?,
+            port.clone(),
+            check_ssl,
+            CONFIG.clone()
+        ));
+    }
+
+    if c.dhcp == Some(true) {
+        tasks.push(routeros_exporter::spawn_collector!(
+            routeros_exporter::collectors::dhcp::run,
+            (*username).parse()?,
+            (*password).parse()?,
+            (*address).parse()?,
+            port.clone(),
+            check_ssl,
+            CONFIG.clone()
+        ));
+    }
+
+    if c.wireguard == Some(true) {
+        tasks.push(routeros_exporter::spawn_collector!(
+            routeros_exporter::collectors::wireguard::run,
+            (*username).parse()?,
+            (*password).parse()?,
+            (*address).parse()?,
+            port.clone(),
+            check_ssl,
+            CONFIG.clone()
+        ));
+    }
+
+    if c.ipsec == Some(true) {
+        tasks.push(routeros_exporter::spawn_collector!(
+            routeros_exporter::collectors::ipsec::run,
+            (*username).parse()?,
+            (*password).parse()?,
+            (*address).parse()?,
+            port.clone(),
+            check_ssl,
+            CONFIG.clone()
+        ));
+    }
+
+    if c.ppp == Some(true) {
+        tasks.push(routeros_exporter::spawn_collector!(
+            routeros_exporter::collectors::ppp::run,
+            (*username).parse()?,
+            (*password).parse()?,
+            (*address).parse()?,
+            port.clone(),
+            check_ssl,
+            CONFIG.clone()
+        ));
+    }
+
+    // One collector per kind of queue
+    for kind in Kind::ALL {
+        if c.get(&kind.collector()) == Some(true) {
             tasks.push(routeros_exporter::spawn_collector!(
-                routeros_exporter::collectors::ip_firewall_raw::run,
+                routeros_exporter::collectors::queue::run,
+                kind,
                 (*username).parse()?,
                 (*password).parse()?,
                 (*address).parse()?,
@@ -189,9 +225,21 @@ async fn http_get_probe(q: Vec<(String, String)>) -> Result<impl warp::Reply, In
         }
     }
 
-    if c.health == Some(true) {
+    if c.wireless == Some(true) {
         tasks.push(routeros_exporter::spawn_collector!(
-            routeros_exporter::collectors::system_health::run,
+            routeros_exporter::collectors::wireless::run,
+            (*username).parse()?,
+            (*password).parse()?,
+            (*address).parse()?,
+            port.clone(),
+            check_ssl,
+            CONFIG.clone()
+        ));
+    }
+
+    if c.capsman == Some(true) {
+        tasks.push(routeros_exporter::spawn_collector!(
+            routeros_exporter::collectors::capsman::run,
             (*username).parse()?,
             (*password).parse()?,
             (*address).parse()?,
@@ -215,6 +263,45 @@ async fn http_get_probe(q: Vec<(String, String)>) -> Result<impl warp::Reply, In
         .body(ret))
 }
 
+// HTTP GET /debug/collectors
+// Shows the effective configuration for a probe request, without polling the target
+async fn http_get_debug_collectors(
+    q: Vec<(String, String)>,
+) -> Result<impl warp::Reply, Infallible> {
+    let probe = match resolve(&CONFIG, &q) {
+        Ok(p) => p,
+        Err(e) => return Ok(http_probe_error(e)),
+    };
+
+    // Whether each collector will run, with the same gating as /probe
+    let collectors: serde_json::Map<String, serde_json::Value> = Collectors::NAMES
+        .iter()
+        .filter(|name| !Collectors::NOT_IMPLEMENTED.contains(name))
+        .map(|name| (name.to_string(), probe.collectors.enabled(name).into()))
+        .collect();
+
+    let body = serde_json::json!({
+        "address": probe.address,
+        "port": probe.port,
+        "username": probe.username,
+        "check_ssl": probe.check_ssl,
+        "collectors": collectors,
+        "interface_filter": probe.interface_filter,
+    });
+
+    Ok(warp::http::Response::builder()
+        .header("Content-Type", "application/json")
+        .body(serde_json::to_string_pretty(&body).unwrap()))
+}
+
+// Error response for probe requests that can't be satisfied
+fn http_probe_error(e: ProbeError) -> Result<warp::http::Response<String>, warp::http::Error> {
+    warp::http::Response::builder()
+        .status(e.status())
+        .header("Content-Type", "text/plain")
+        .body(e.to_string())
+}
+
 // HTTP GET /
 async fn http_get_root() -> Result<impl warp::Reply, Infallible> {
     Ok(warp::http::Response::builder()
@@ -230,8 +317,12 @@ async fn main() {
     let probe = warp::path("probe")
         .and(warp::query::<Vec<(String, String)>>())
         .and_then(http_get_probe);
+    // /debug/collectors?<query>
+    let debug_collectors = warp::path!("debug" / "collectors")
+        .and(warp::query::<Vec<(String, String)>>())
+        .and_then(http_get_debug_collectors);
     // Combine the above to a group of routes
-    let routes = warp::get().and(root.or(probe));
+    let routes = warp::get().and(root.or(probe).or(debug_collectors));
     // Build listen address from config
     // TODO: address to bind to
     let addr = SocketAddr::from(([0, 0, 0, 0], CONFIG.server.port));
@@ -0,0 +1,329 @@
+use secrecy::Secret;
+
+use crate::configuration::{Collectors, InterfaceFilter, InterfaceMatcher, Settings};
+use crate::target::{Host, Target};
+
+// Effective configuration for a single probe request
+//
+// Each layer only overrides what it sets, in order of precedence (lowest first)
+//   1. [defaults]
+//   2. [auths.<name>] (credentials) and [modules.<name>] (collectors and interface filter),
+//      selected with ?auth= and ?module=
+//   3. [[instances]] whose address matches the target
+//   4. query parameters, ?collectors=, ?exclude_collectors=, ?port=, ?check_ssl, and a port given in ?target=
+pub struct Probe {
+    pub username: String,
+    pub password: Secret<String>,
+    pub address: String,
+    pub port: u16,
+    pub check_ssl: bool,
+    pub collectors: Collectors,
+    pub interface_filter: InterfaceFilter,
+    pub interfaces: InterfaceMatcher,
+}
+
+#[derive(Debug)]
+pub enum ProbeError {
+    // Something wrong with the request its self
+    BadRequest(String),
+    // Something wrong with the configuration for this target
+    Configuration(String),
+}
+
+impl std::fmt::Display for ProbeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProbeError::BadRequest(m) => write!(f, "{}", m),
+            ProbeError::Configuration(m) => write!(f, "{}", m),
+        }
+    }
+}
+
+impl std::error::Error for ProbeError {}
+
+impl ProbeError {
+    // Status of the probe response
+    pub fn status(&self) -> warp::http::StatusCode {
+        match self {
+            ProbeError::BadRequest(_) => warp::http::StatusCode::BAD_REQUEST,
+            ProbeError::Configuration(_) => warp::http::StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+pub fn resolve(config: &Settings, q: &[(String, String)]) -> Result<Probe, ProbeError> {
+    // Start with defaults
+    let mut c = config.defaults.collectors.clone().unwrap_or_default();
+    let mut port = config.defaults.port;
+    let mut username = config.defaults.username.clone();
+    let mut password = config.defaults.resolve_password("defaults");
+    let mut address = config.defaults.address.clone();
+    let mut check_ssl = config.defaults.check_ssl;
+    let mut interface_filter = config.defaults.interface_filter.clone().unwrap_or_default();
+
+    // If target, auth or module in query, set it now
+    let mut auth = None;
+    let mut module = None;
+    // Only used to pick the instance, ?port= is validated with the other query parameters
+    let mut query_port = None;
+    for (k, v) in q {
+        match k.as_str() {
+            "target" => address = v.clone(),
+            "auth" => auth = Some(v),
+            "module" => module = Some(v),
+            "port" => query_port = v.parse::<u16>().ok(),
+            _ => {}
+        }
+    }
+
+    // Named auth profile, overrides the defaults
+    if let Some(name) = auth {
+        match config.auths.as_ref().and_then(|a| a.get(name)) {
+            Some(profile) => {
+                username = profile.username.clone();
+                password = profile.resolve_password(&format!("auth '{}'", name));
+                port = profile.port;
+                check_ssl = profile.check_ssl;
+            }
+            None => return Err(ProbeError::BadRequest(format!("unknown auth '{}'", name))),
+        }
+    }
+
+    // Named module, merged over the default collectors
+    if let Some(name) = module {
+        match config.modules.as_ref().and_then(|m| m.get(name)) {
+            Some(m) => {
+                c = c.merge(&m.collectors);
+                if let Some(filter) = m.interface_filter.as_ref() {
+                    interface_filter = interface_filter.merge(filter);
+                }
+            }
+            None => return Err(ProbeError::BadRequest(format!("unknown module '{}'", name))),
+        }
+    }
+
+    // Split the target into host and optional port
+    let target = address
+        .parse::<Target>()
+        .map_err(|e| ProbeError::BadRequest(e.to_string()))?;
+    address = target.host.to_string();
+
+    // If the target is defined in config, override, this takes precedence over auth and module
+    // Addresses are compared in normalized form, so 2001:DB8::1 matches [2001:db8::1]
+    let requested_port = target.port.or(query_port);
+    if let Some(instances) = config.instances.as_ref() {
+        for instance in instances {
+            let instance_target = match instance.address.parse::<Target>() {
+                Ok(t) => t,
+                Err(_) => continue,
+            };
+            if instance_matches(
+                &target.host,
+                requested_port,
+                &instance_target,
+                instance.port,
+            ) {
+                if let Some(collectors) = instance.collectors.as_ref() {
+                    c = c.merge(collectors);
+                }
+                if let Some(filter) = instance.interface_filter.as_ref() {
+                    interface_filter = interface_filter.merge(filter);
+                }
+                username = instance.username.clone();
+                password = instance.resolve_password(&format!("instance '{}'", instance.address));
+                port = instance_target.port.unwrap_or(instance.port);
+                check_ssl = instance.check_ssl;
+            }
+        }
+    }
+
+    // A port given as part of the target overrides the configured port
+    if let Some(p) = target.port {
+        port = p;
+    }
+
+    // Only fail on the password that is actually used
+    let password = password.map_err(|e| ProbeError::Configuration(e.to_string()))?;
+
+    // Try and match query params
+    // collectors and exclude_collectors can be given multiple times, and/or comma separated
+    let mut include = vec![];
+    let mut exclude = vec![];
+    for (k, v) in q {
+        match k.as_str() {
+            "collectors" => include.extend(split_collectors(v)?),
+            "exclude_collectors" => exclude.extend(split_collectors(v)?),
+            "check_ssl" => {
+                check_ssl = match v.as_str() {
+                    "" | "true" | "1" => true,
+                    "false" | "0" => false,
+                    _ => return Err(ProbeError::BadRequest(format!("invalid check_ssl '{}'", v))),
+                }
+            }
+            //  Removing as this shouldn't be over the wire
+            // "username" => {
+            //     username = v.as_str().parse()?;
+            // }
+            // "password" => {
+            //     password = v;
+            // }
+            "port" => {
+                port = match v.parse::<u16>() {
+                    Ok(p) if p != 0 => p,
+                    _ => return Err(ProbeError::BadRequest(format!("invalid port '{}'", v))),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Excluding wins over including the same collector
+    for name in include {
+        *c.get_mut(name).unwrap() = Some(true);
+    }
+    for name in exclude {
+        *c.get_mut(name).unwrap() = Some(false);
+    }
+
+    // Invalid patterns are a configuration error for this target
+    let interfaces = interface_filter
+        .matcher()
+        .map_err(|e| ProbeError::Configuration(format!("invalid interface_filter: {}", e)))?;
+
+    Ok(Probe {
+        username,
+        password,
+        address,
+        port,
+        check_ssl,
+        collectors: c,
+        interface_filter,
+        interfaces,
+    })
+}
+
+// Routers behind one address are told apart by port, so when either the request or the instance
+// address names a port, the ports must match too. An instance's port is the one in its address,
+// or else its port setting
+fn instance_matches(
+    host: &Host,
+    requested_port: Option<u16>,
+    instance: &Target,
+    instance_port: u16,
+) -> bool {
+    if instance.host != *host {
+        return false;
+    }
+    match (requested_port, instance.port) {
+        (None, None) => true,
+        (Some(p), _) => p == instance.port.unwrap_or(instance_port),
+        (None, Some(_)) => false,
+    }
+}
+
+// Split a comma separated list of collectors, checking each is a known collector. Collectors that
+// aren't implemented are accepted, as older configurations list them, but never run
+fn split_collectors(v: &str) -> Result<Vec<&str>, ProbeError> {
+    let mut names = vec![];
+    for name in v.split(',').map(str::trim).filter(|n| !n.is_empty()) {
+        if !Collectors::NAMES.contains(&name) {
+            let known: Vec<&str> = Collectors::NAMES
+                .iter()
+                .filter(|n| !Collectors::NOT_IMPLEMENTED.contains(n))
+                .copied()
+                .collect();
+            return Err(ProbeError::BadRequest(format!(
+                "unknown collector '{}', expected one of {}",
+                name,
+                known.join(", ")
+            )));
+        }
+        names.push(name);
+    }
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(target: &str, query_port: Option<u16>, instance: &str, port: u16) -> bool {
+        let target = target.parse::<Target>().unwrap();
+        instance_matches(
+            &target.host,
+            target.port.or(query_port),
+            &instance.parse::<Target>().unwrap(),
+            port,
+        )
+    }
+
+    fn settings(extra: &str) -> Settings {
+        let mut settings = config::Config::default();
+        settings
+            .merge(config::File::from_str(
+                &format!(
+                    "metrics_prefix = \"routeros\"\n{}\n\
+                     [server]\nport = 9886\n\
+                     [defaults]\nusername = \"u\"\npassword = \"p\"\ncheck_ssl = false\n\
+                     address = \"192.168.88.1\"\nport = 443\n",
+                    extra
+                ),
+                config::FileFormat::Toml,
+            ))
+            .unwrap();
+        settings.try_into().unwrap()
+    }
+
+    fn query(q: &[(&str, &str)]) -> Vec<(String, String)> {
+        q.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn invalid_interface_filter_is_a_server_error() {
+        let config = settings(
+            "[[instances]]\nusername = \"u\"\npassword = \"p\"\ncheck_ssl = false\n\
+             address = \"gw\"\nport = 443\n\
+             [instances.interface_filter]\ninclude_name = \"ether(\"\n",
+        );
+        match resolve(&config, &query(&[("target", "gw")])) {
+            Err(e @ ProbeError::Configuration(_)) => {
+                assert_eq!(e.status(), warp::http::StatusCode::INTERNAL_SERVER_ERROR)
+            }
+            _ => panic!("expected a configuration error"),
+        }
+        assert!(resolve(&config, &query(&[("target", "other")])).is_ok());
+    }
+
+    #[test]
+    fn collectors() {
+        assert_eq!(
+            split_collectors("health, interfaces,,bgp").unwrap(),
+            vec!["health", "interfaces", "bgp"]
+        );
+        assert!(split_collectors("health,nope").is_err());
+        assert_eq!(
+            split_collectors("resources,ip_firewall_conntrack").unwrap(),
+            vec!["resources", "ip_firewall_conntrack"]
+        );
+    }
+
+    #[test]
+    fn instances_by_host() {
+        assert!(matches("gw", None, "gw", 443));
+        assert!(matches("2001:DB8::1", None, "[2001:db8::1]", 443));
+        assert!(!matches("gw2", None, "gw", 443));
+    }
+
+    #[test]
+    fn instances_by_port() {
+        assert!(matches("gw:8443", None, "gw:8443", 443));
+        assert!(!matches("gw:8443", None, "gw:8444", 443));
+        assert!(matches("gw", Some(8443), "gw:8443", 443));
+        // The instance's port setting, when its address has no port
+        assert!(matches("gw:8443", None, "gw", 8443));
+        assert!(!matches("gw:8443", None, "gw", 443));
+        assert!(!matches("gw", None, "gw:8443", 443));
+    }
+}
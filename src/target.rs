@@ -0,0 +1,274 @@
+use std::fmt;
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+// Host part of a probe target, in normalized form
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Host {
+    Ipv4(Ipv4Addr),
+    Ipv6(Ipv6Addr),
+    Name(String),
+}
+
+// A probe target, as passed in ?target= or configured as an instance address
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Target {
+    pub host: Host,
+    pub port: Option<u16>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TargetError {
+    Empty,
+    InvalidHost(String),
+    InvalidPort(String),
+}
+
+impl fmt::Display for TargetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TargetError::Empty => write!(f, "target is empty"),
+            TargetError::InvalidHost(h) => write!(f, "invalid target host '{}'", h),
+            TargetError::InvalidPort(p) => write!(f, "invalid target port '{}'", p),
+        }
+    }
+}
+
+impl std::error::Error for TargetError {}
+
+impl Host {
+    // Host as it should appear in a URL, IPv6 literals are bracketed
+    pub fn url_host(&self) -> String {
+        match self {
+            Host::Ipv6(a) => format!("[{}]", a),
+            _ => self.to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Host {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Host::Ipv4(a) => write!(f, "{}", a),
+            Host::Ipv6(a) => write!(f, "{}", a),
+            Host::Name(n) => write!(f, "{}", n),
+        }
+    }
+}
+
+impl std::str::FromStr for Host {
+    type Err = TargetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(TargetError::Empty);
+        }
+        if let Ok(a) = s.parse::<Ipv4Addr>() {
+            return Ok(Host::Ipv4(a));
+        }
+        if let Ok(a) = s.parse::<Ipv6Addr>() {
+            return Ok(Host::Ipv6(a));
+        }
+
+        // DNS names are case insensitive, and a trailing dot is the same name
+        let name = s.strip_suffix('.').unwrap_or(s).to_ascii_lowercase();
+        let valid_label = |l: &str| {
+            !l.is_empty()
+                && l.len() <= 63
+                && !l.starts_with('-')
+                && !l.ends_with('-')
+                && l.chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        };
+        if name.is_empty() || name.len() > 253 || !name.split('.').all(valid_label) {
+            return Err(TargetError::InvalidHost(s.to_string()));
+        }
+        Ok(Host::Name(name))
+    }
+}
+
+impl std::str::FromStr for Target {
+    type Err = TargetError;
+
+    // Accepts
+    //   192.168.88.1, 192.168.88.1:8443
+    //   2001:db8::1, [2001:db8::1], [2001:db8::1]:8443
+    //   router1.example.com, router1.example.com:8443
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err(TargetError::Empty);
+        }
+
+        // Bracketed IPv6, optionally with a port
+        if let Some(rest) = s.strip_prefix('[') {
+            let (addr, after) = rest
+                .split_once(']')
+                .ok_or_else(|| TargetError::InvalidHost(s.to_string()))?;
+            let host = addr
+                .parse::<Ipv6Addr>()
+                .map(Host::Ipv6)
+                .map_err(|_| TargetError::InvalidHost(addr.to_string()))?;
+            let port = match after {
+                "" => None,
+                _ => match after.strip_prefix(':') {
+                    Some(p) => Some(parse_port(p)?),
+                    None => return Err(TargetError::InvalidPort(after.to_string())),
+                },
+            };
+            return Ok(Target { host, port });
+        }
+
+        // Bare IPv6, which can't carry a port
+        if let Ok(a) = s.parse::<Ipv6Addr>() {
+            return Ok(Target {
+                host: Host::Ipv6(a),
+                port: None,
+            });
+        }
+
+        match s.split_once(':') {
+            Some((host, port)) => Ok(Target {
+                host: host.parse()?,
+                port: Some(parse_port(port)?),
+            }),
+            None => Ok(Target {
+                host: s.parse()?,
+                port: None,
+            }),
+        }
+    }
+}
+
+fn parse_port(p: &str) -> Result<u16, TargetError> {
+    match p.parse::<u16>() {
+        Ok(port) if port != 0 => Ok(port),
+        _ => Err(TargetError::InvalidPort(p.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn target(s: &str) -> Result<Target, TargetError> {
+        s.parse::<Target>()
+    }
+
+    #[test]
+    fn ipv4() {
+        assert_eq!(
+            target("192.168.88.1"),
+            Ok(Target {
+                host: Host::Ipv4(Ipv4Addr::new(192, 168, 88, 1)),
+                port: None,
+            })
+        );
+        assert_eq!(target("192.168.88.1:8443").unwrap().port, Some(8443));
+    }
+
+    #[test]
+    fn ipv6_bare_and_bracketed() {
+        let addr: Ipv6Addr = "2001:db8::1".parse().unwrap();
+        for s in [
+            "2001:db8::1",
+            "[2001:db8::1]",
+            "2001:DB8:0::1",
+            " [2001:db8::1] ",
+        ] {
+            assert_eq!(
+                target(s),
+                Ok(Target {
+                    host: Host::Ipv6(addr),
+                    port: None,
+                }),
+                "{}",
+                s
+            );
+        }
+        assert_eq!(
+            target("[2001:db8::1]:8443"),
+            Ok(Target {
+                host: Host::Ipv6(addr),
+                port: Some(8443),
+            })
+        );
+        assert_eq!(target("::1").unwrap().host, Host::Ipv6(Ipv6Addr::LOCALHOST));
+    }
+
+    #[test]
+    fn ipv6_invalid() {
+        assert_eq!(
+            target("[::1]x"),
+            Err(TargetError::InvalidPort("x".to_string()))
+        );
+        assert_eq!(
+            target("[::1]:"),
+            Err(TargetError::InvalidPort("".to_string()))
+        );
+        assert_eq!(
+            target("[::1"),
+            Err(TargetError::InvalidHost("[::1".to_string()))
+        );
+        assert_eq!(
+            target("[192.168.88.1]"),
+            Err(TargetError::InvalidHost("192.168.88.1".to_string()))
+        );
+        // Without brackets the port can't be told apart from the address
+        assert!(target("2001:db8::1:8443x").is_err());
+    }
+
+    #[test]
+    fn names() {
+        assert_eq!(
+            target("Router1.Example.com."),
+            Ok(Target {
+                host: Host::Name("router1.example.com".to_string()),
+                port: None,
+            })
+        );
+        assert_eq!(
+            target("router1.example.com:8443"),
+            Ok(Target {
+                host: Host::Name("router1.example.com".to_string()),
+                port: Some(8443),
+            })
+        );
+        assert_eq!(
+            target("gw_1"),
+            Ok(Target {
+                host: Host::Name("gw_1".to_string()),
+                port: None,
+            })
+        );
+        for s in ["-gw", "gw-", "a..b", ".", "gw/1", "gw 1", "https://gw"] {
+            assert!(target(s).is_err(), "{}", s);
+        }
+        assert!(target(&format!("{}.com", "a".repeat(64))).is_err());
+    }
+
+    #[test]
+    fn ports() {
+        assert_eq!(target("gw:65535").unwrap().port, Some(65535));
+        for p in ["0", "65536", "", "-1", "x"] {
+            assert_eq!(
+                target(&format!("gw:{}", p)),
+                Err(TargetError::InvalidPort(p.to_string())),
+                "{}",
+                p
+            );
+        }
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(target(""), Err(TargetError::Empty));
+        assert_eq!(target("  "), Err(TargetError::Empty));
+        assert_eq!(target(":8443"), Err(TargetError::Empty));
+    }
+
+    #[test]
+    fn url_host() {
+        assert_eq!(target("[::1]:8443").unwrap().host.url_host(), "[::1]");
+        assert_eq!(target("10.0.0.1").unwrap().host.url_host(), "10.0.0.1");
+    }
+}